@@ -0,0 +1,288 @@
+use crate::protocol::types::{GameProfile, TextComponent};
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Lists of player names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessList {
+    Whitelist,
+    Ops,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BanKind {
+    Uuid,
+    Name,
+    Ip,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub target: String,
+    pub reason: String,
+    pub source: String,
+    /// Unix time in seconds.
+    pub created: u64,
+    /// Unix time in seconds, `None` for permanent bans.
+    pub expires: Option<u64>,
+}
+
+impl BanEntry {
+    pub fn new(target: impl Into<String>, reason: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            reason: reason.into(),
+            source: source.into(),
+            created: unix_now(),
+            expires: None,
+        }
+    }
+
+    pub fn expires_in(mut self, duration: Duration) -> Self {
+        self.expires = Some(unix_now() + duration.as_secs());
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= unix_now())
+    }
+
+    /// Disconnect message shown to the banned player.
+    pub fn message(&self) -> TextComponent {
+        self.message_with("You are banned from this server.")
+    }
+
+    /// Disconnect message shown to players joining from a banned IP address.
+    pub fn ip_message(&self) -> TextComponent {
+        self.message_with("Your IP address is banned from this server.")
+    }
+
+    fn message_with(&self, header: &str) -> TextComponent {
+        let mut message = TextComponent::new(header).color("red");
+        if !self.reason.is_empty() {
+            message = message.append(format!("\nReason: {}", self.reason));
+        }
+        if let Some(expires) = self.expires {
+            let remaining = expires.saturating_sub(unix_now());
+            message = message.append(format!("\nYour ban expires in {}", format_duration(remaining)));
+        }
+        message
+    }
+}
+
+/// Whitelist, bans and operators kept in the server storage. Names are stored lowercase.
+#[derive(Clone)]
+pub struct AccessLists {
+    storage: Arc<dyn Storage>,
+}
+
+impl AccessLists {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Reason to refuse the login, if any.
+    pub async fn check_login(
+        &self,
+        profile: &GameProfile,
+        ip: IpAddr,
+        whitelist_enabled: bool,
+    ) -> anyhow::Result<Option<TextComponent>> {
+        if let Some(ban) = self.ban(BanKind::Ip, &ip.to_string()).await? {
+            return Ok(Some(ban.ip_message()));
+        }
+        if let Some(ban) = self.ban(BanKind::Uuid, &profile.uuid.to_string()).await? {
+            return Ok(Some(ban.message()));
+        }
+        if let Some(ban) = self.ban(BanKind::Name, &profile.name).await? {
+            return Ok(Some(ban.message()));
+        }
+        if whitelist_enabled && !self.is_whitelisted(&profile.name).await? && !self.is_op(&profile.name).await? {
+            return Ok(Some(TextComponent::new("You are not whitelisted on this server!")));
+        }
+
+        Ok(None)
+    }
+
+    pub async fn whitelist_add(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage.list_add(AccessList::Whitelist, &name.to_lowercase()).await
+    }
+
+    pub async fn whitelist_remove(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage
+            .list_remove(AccessList::Whitelist, &name.to_lowercase())
+            .await
+    }
+
+    pub async fn is_whitelisted(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage
+            .list_contains(AccessList::Whitelist, &name.to_lowercase())
+            .await
+    }
+
+    pub async fn whitelist(&self) -> anyhow::Result<Vec<String>> {
+        self.storage.list_members(AccessList::Whitelist).await
+    }
+
+    pub async fn op_add(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage.list_add(AccessList::Ops, &name.to_lowercase()).await
+    }
+
+    pub async fn op_remove(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage.list_remove(AccessList::Ops, &name.to_lowercase()).await
+    }
+
+    pub async fn is_op(&self, name: &str) -> anyhow::Result<bool> {
+        self.storage.list_contains(AccessList::Ops, &name.to_lowercase()).await
+    }
+
+    pub async fn ops(&self) -> anyhow::Result<Vec<String>> {
+        self.storage.list_members(AccessList::Ops).await
+    }
+
+    pub async fn ban_add(&self, kind: BanKind, mut entry: BanEntry) -> anyhow::Result<()> {
+        entry.target = normalize_target(kind, &entry.target);
+        self.storage.ban_add(kind, entry).await
+    }
+
+    pub async fn ban_remove(&self, kind: BanKind, target: &str) -> anyhow::Result<bool> {
+        self.storage.ban_remove(kind, &normalize_target(kind, target)).await
+    }
+
+    /// Active ban of the target. Expired bans are removed on lookup.
+    pub async fn ban(&self, kind: BanKind, target: &str) -> anyhow::Result<Option<BanEntry>> {
+        let target = normalize_target(kind, target);
+        match self.storage.ban(kind, &target).await? {
+            Some(entry) if entry.is_expired() => {
+                self.storage.ban_remove(kind, &target).await?;
+                Ok(None)
+            }
+            entry => Ok(entry),
+        }
+    }
+
+    pub async fn bans(&self, kind: BanKind) -> anyhow::Result<Vec<BanEntry>> {
+        let mut bans = self.storage.bans(kind).await?;
+        bans.retain(|entry| !entry.is_expired());
+        Ok(bans)
+    }
+}
+
+fn normalize_target(kind: BanKind, target: &str) -> String {
+    match kind {
+        BanKind::Uuid => Uuid::parse_str(target).map_or_else(|_| target.to_lowercase(), |uuid| uuid.to_string()),
+        BanKind::Name => target.to_lowercase(),
+        BanKind::Ip => target.to_string(),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s >= 86400 => format!("{}d {}h", s / 86400, s % 86400 / 3600),
+        s if s >= 3600 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[tokio::test]
+    async fn test_access_lists() {
+        let access = AccessLists::new(Arc::new(MemoryStorage::new()));
+        let profile = GameProfile::new(Uuid::nil(), "Notch");
+        let ip = "192.0.2.1".parse().unwrap();
+
+        assert!(access.whitelist_add("Notch").await.unwrap());
+        assert!(access.is_whitelisted("NOTCH").await.unwrap());
+        assert!(access.check_login(&profile, ip, true).await.unwrap().is_none());
+
+        access
+            .ban_add(BanKind::Name, BanEntry::new("NOTCH", "griefing", "Server"))
+            .await
+            .unwrap();
+        assert!(access.check_login(&profile, ip, true).await.unwrap().is_some());
+        assert!(access.ban_remove(BanKind::Name, "notch").await.unwrap());
+
+        let expired = BanEntry::new("192.0.2.1", "", "Server").expires_in(Duration::ZERO);
+        access.ban_add(BanKind::Ip, expired).await.unwrap();
+        assert!(access.check_login(&profile, ip, true).await.unwrap().is_none());
+        assert!(access.bans(BanKind::Ip).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_ban() {
+        let access = AccessLists::new(Arc::new(MemoryStorage::new()));
+        let profile = GameProfile::new(Uuid::nil(), "Notch");
+        let ip = "192.0.2.1".parse().unwrap();
+
+        let expired = BanEntry::new(Uuid::nil().to_string(), "griefing", "Server").expires_in(Duration::ZERO);
+        access.ban_add(BanKind::Uuid, expired).await.unwrap();
+        assert!(access.check_login(&profile, ip, false).await.unwrap().is_none());
+        assert!(access.ban(BanKind::Uuid, &Uuid::nil().to_string()).await.unwrap().is_none());
+
+        let temporary = BanEntry::new("Notch", "", "Server").expires_in(Duration::from_secs(7200));
+        access.ban_add(BanKind::Name, temporary).await.unwrap();
+        let message = access.check_login(&profile, ip, false).await.unwrap().unwrap();
+        assert!(message.to_plain().starts_with("You are banned from this server.\nYour ban expires in "));
+    }
+
+    #[tokio::test]
+    async fn test_ip_ban() {
+        let access = AccessLists::new(Arc::new(MemoryStorage::new()));
+        let profile = GameProfile::new(Uuid::nil(), "Notch");
+
+        access
+            .ban_add(BanKind::Ip, BanEntry::new("192.0.2.1", "", "Server"))
+            .await
+            .unwrap();
+        let message = access
+            .check_login(&profile, "192.0.2.1".parse().unwrap(), false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.to_plain(), "Your IP address is banned from this server.");
+        let other_ip = "192.0.2.2".parse().unwrap();
+        assert!(access.check_login(&profile, other_ip, false).await.unwrap().is_none());
+
+        access
+            .ban_add(BanKind::Ip, BanEntry::new("192.0.2.1", "spam", "Server"))
+            .await
+            .unwrap();
+        let message = access
+            .check_login(&profile, "192.0.2.1".parse().unwrap(), false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.to_plain(), "Your IP address is banned from this server.\nReason: spam");
+    }
+
+    #[tokio::test]
+    async fn test_whitelist() {
+        let access = AccessLists::new(Arc::new(MemoryStorage::new()));
+        let profile = GameProfile::new(Uuid::nil(), "Notch");
+        let ip = "192.0.2.1".parse().unwrap();
+
+        assert!(access.check_login(&profile, ip, false).await.unwrap().is_none());
+        let message = access.check_login(&profile, ip, true).await.unwrap().unwrap();
+        assert_eq!(message.to_plain(), "You are not whitelisted on this server!");
+
+        // Operators may join without being whitelisted
+        assert!(access.op_add("notch").await.unwrap());
+        assert!(access.check_login(&profile, ip, true).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use clap::Parser;
+use minecraft_server::registry::TagRegistry;
+use std::fs;
+use std::path::PathBuf;
+
+/// Regenerates the bundled `Update Tags` data from the vanilla data generator output.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Output directory of the data generator, containing `reports/` and `data/`
+    generated: PathBuf,
+    #[arg(long, default_value = "src/registry/tags.json")]
+    output: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let tags = TagRegistry::from_generated(&args.generated)?;
+    fs::write(&args.output, tags.to_json()? + "\n")?;
+
+    println!("Wrote {}", args.output.display());
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use minecraft_server::command::{self, CommandSender};
+use minecraft_server::server::Server;
+use rustyline::error::ReadlineError;
+use rustyline::{DefaultEditor, ExternalPrinter};
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tracing::error;
+
+const PROMPT: &str = "> ";
+const HISTORY_FILE: &str = ".console_history";
+
+/// Admin console on stdin, with line editing and history when attached to a terminal.
+pub struct Console {
+    editor: Option<DefaultEditor>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        let editor = match std::io::stdin().is_terminal() {
+            true => DefaultEditor::new().ok(),
+            false => None,
+        };
+        Self { editor }
+    }
+
+    /// Writer for log lines, printed above the prompt so they don't garble the line being typed.
+    pub fn log_writer(&mut self) -> Box<dyn Write + Send> {
+        match self.editor.as_mut().map(|editor| editor.create_external_printer()) {
+            Some(Ok(printer)) => Box::new(PromptWriter {
+                printer,
+                line: Vec::new(),
+            }),
+            _ => Box::new(std::io::stderr()),
+        }
+    }
+
+    /// Runs commands from stdin on a dedicated thread until stdin is closed or the server stops.
+    ///
+    /// A plain thread rather than a blocking task, so a pending read doesn't hold up the runtime shutdown.
+    pub fn spawn(self, server: Arc<Server>) -> anyhow::Result<()> {
+        let runtime = Handle::current();
+        std::thread::Builder::new()
+            .name("console".into())
+            .spawn(move || self.run(&runtime, &server))?;
+        Ok(())
+    }
+
+    fn run(mut self, runtime: &Handle, server: &Server) {
+        if let Some(editor) = self.editor.as_mut() {
+            let _ = editor.load_history(HISTORY_FILE);
+        }
+
+        while !server.is_shutting_down() {
+            let line = match self.read_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    error!(error = %e, "Console error");
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let output = runtime.block_on(command::execute(server, &CommandSender::Console, &line));
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+        }
+
+        if let Some(editor) = self.editor.as_mut() {
+            let _ = editor.save_history(HISTORY_FILE);
+        }
+    }
+
+    fn read_line(&mut self) -> anyhow::Result<Option<String>> {
+        let Some(editor) = self.editor.as_mut() else {
+            let mut line = String::new();
+            return Ok((std::io::stdin().read_line(&mut line)? > 0).then_some(line));
+        };
+
+        match editor.readline(PROMPT) {
+            Ok(line) => {
+                let _ = editor.add_history_entry(line.as_str());
+                Ok(Some(line))
+            }
+            // The terminal is in raw mode, so Ctrl-C arrives here instead of as a signal
+            Err(ReadlineError::Interrupted) => Ok(Some("stop".into())),
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Buffers writes and hands complete lines to the editor's external printer.
+struct PromptWriter<P> {
+    printer: P,
+    line: Vec<u8>,
+}
+
+impl<P: ExternalPrinter> Write for PromptWriter<P> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        if let Some(end) = self.line.iter().rposition(|&byte| byte == b'\n') {
+            let lines: Vec<u8> = self.line.drain(..=end).collect();
+            self.printer
+                .print(String::from_utf8_lossy(&lines).into_owned())
+                .map_err(std::io::Error::other)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
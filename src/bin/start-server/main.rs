@@ -1,108 +1,332 @@
+mod console;
+
 use anyhow::{bail, Result};
 use clap::Parser;
-use deadpool_redis::redis::AsyncCommands;
-use deadpool_redis::Runtime;
-use deadpool_redis::{Config as RedisConfig, Pool};
-use log::{error, info};
-use minecraft_server::connection::request::{ReadRequest, Request};
+use console::Console;
+use minecraft_server::command::{self, CommandSender};
+use minecraft_server::config::{LogFormat, LoggingConfig, ServerConfig, StorageBackend};
+use minecraft_server::connection::forwarding::{
+    parse_velocity_player_info, ForwardingMode, VELOCITY_CHANNEL, VELOCITY_FORWARDING_VERSION,
+};
+use minecraft_server::connection::request::{Incoming, ReadRequest, Request};
 use minecraft_server::connection::response::{Response, SendResponse};
 use minecraft_server::connection::ClientConnection;
-use minecraft_server::protocol::types::enums::{ClientState, GameMode};
-use tokio::net::{TcpListener, TcpStream};
+use minecraft_server::diagnostics;
+use minecraft_server::game::{self, PlayerAction, PlayerData};
+use minecraft_server::metrics::{self, METRICS};
+use minecraft_server::plugin::{PlayerPreLoginEvent, WasmHost};
+use minecraft_server::protocol::ConnectionClosed;
+use minecraft_server::protocol::types::enums::{ClientState, PlayerActionStatus};
+use minecraft_server::protocol::types::{GameProfile, TextComponent};
+use minecraft_server::server::{is_valid_username, offline_uuid, Server};
+use minecraft_server::{proxy_protocol, query, rcon, storage};
+use std::io::{IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tracing::{debug, debug_span, error, field, info, info_span, warn, Instrument, Span};
+use tracing_subscriber::EnvFilter;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(long, default_value = "0.0.0.0")]
-    host: String,
-    #[arg(long, default_value_t = 25565)]
-    port: u16,
-    #[arg(long, default_value = "redis://localhost:6379")]
-    redis_url: String,
-    #[arg(long, default_value_t = 10)]
-    redis_pool_size: usize,
+    /// Path to the TOML config file [default: server.toml, if present]
+    #[arg(long, env = "MC_CONFIG")]
+    config: Option<PathBuf>,
+    /// Print the default config file and exit
+    #[arg(long)]
+    print_default_config: bool,
+    #[arg(long, env = "MC_HOST")]
+    host: Option<String>,
+    #[arg(long, env = "MC_PORT")]
+    port: Option<u16>,
+    /// Storage backend: redis or memory
+    #[arg(long, env = "MC_STORAGE")]
+    storage: Option<StorageBackend>,
+    #[arg(long, env = "MC_REDIS_URL")]
+    redis_url: Option<String>,
+    #[arg(long, env = "MC_REDIS_POOL_SIZE")]
+    redis_pool_size: Option<usize>,
+    #[arg(long, env = "MC_MAX_PLAYERS")]
+    max_players: Option<u32>,
+    /// Enable RCON on this port
+    #[arg(long, env = "MC_RCON_PORT")]
+    rcon_port: Option<u16>,
+    #[arg(long, env = "MC_RCON_PASSWORD", hide_env_values = true)]
+    rcon_password: Option<String>,
+    /// Log output format: text or json
+    #[arg(long, env = "MC_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
 }
 
-const SERVER_INFO: &str = r#"{
-                    "version": {
-                        "name": "1.21.5",
-                        "protocol": 770
-                    },
-                    "players": {
-                        "max": 2,
-                        "online": 0,
-                        "sample": []
-                    },
-                    "description": {
-                        "text": "Rust Minecraft Server"
-                    }
-                }"#;
+const DEFAULT_CONFIG_PATH: &str = "server.toml";
+const VELOCITY_MESSAGE_ID: i32 = 1;
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const STORAGE_UNAVAILABLE_MESSAGE: &str = "The server can't accept logins right now, please try again later.";
+
+impl Args {
+    fn load_config(&self) -> Result<ServerConfig> {
+        let mut config = match &self.config {
+            Some(path) => ServerConfig::load(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => ServerConfig::load(Path::new(DEFAULT_CONFIG_PATH))?,
+            None => ServerConfig::default(),
+        };
+
+        if let Some(host) = &self.host {
+            config.network.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.network.port = port;
+        }
+        if let Some(storage) = self.storage {
+            config.storage.backend = storage;
+        }
+        if let Some(redis_url) = &self.redis_url {
+            config.redis.url = redis_url.clone();
+        }
+        if let Some(redis_pool_size) = self.redis_pool_size {
+            config.redis.pool_size = redis_pool_size;
+        }
+        if let Some(max_players) = self.max_players {
+            config.gameplay.max_players = max_players;
+        }
+        if let Some(rcon_port) = self.rcon_port {
+            config.rcon.enabled = true;
+            config.rcon.port = rcon_port;
+        }
+        if let Some(rcon_password) = &self.rcon_password {
+            config.rcon.password = rcon_password.clone();
+        }
+        if let Some(log_format) = self.log_format {
+            config.logging.format = log_format;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::init();
-
     let args = Args::parse();
-    let (host, port) = (args.host, args.port);
+    if args.print_default_config {
+        print!("{}", ServerConfig::default().to_toml());
+        return Ok(());
+    }
+
+    let config = args.load_config()?;
+    let mut console = Console::new();
+    init_tracing(&config.logging, console.log_writer())?;
+    let (host, port) = (config.network.host.clone(), config.network.port);
+
+    let storage = storage::open(&config)?;
+    info!(backend = storage.name(), "Opened storage");
 
-    let redis_pool = RedisConfig::from_url(args.redis_url)
-        .builder()?
-        .max_size(args.redis_pool_size)
-        .runtime(Runtime::Tokio1)
-        .build()?;
+    let wasm_host = match config.plugins.enabled {
+        true => Some(WasmHost::new(&config.plugins)?),
+        false => None,
+    };
+    let mut builder = Server::builder(config, storage);
+    if let Some(wasm_host) = &wasm_host {
+        builder = builder.plugin(wasm_host.clone());
+    }
+    let server = Arc::new(builder.build()?);
+    console.spawn(server.clone())?;
+
+    if let Some(wasm_host) = wasm_host {
+        tokio::spawn(wasm_host.run(server.clone()));
+    }
+    let game_loop = game::spawn(server.clone())?;
+    if server.config.diagnostics.watchdog {
+        diagnostics::spawn_watchdog(server.clone())?;
+    }
 
     let listener = TcpListener::bind(format!("{host}:{port}")).await?;
 
-    loop {
-        let (stream, addr) = listener.accept().await?;
-        let redis_pool = redis_pool.clone();
+    if server.config.rcon.enabled {
+        let rcon_listener = TcpListener::bind(format!("{host}:{}", server.config.rcon.port)).await?;
+        let password = server.config.rcon.password.clone();
+        tokio::spawn(rcon::listen(server.clone(), rcon_listener, password));
+    }
 
-        tokio::spawn(async move {
-            info!("Connection with: {}", addr);
+    if server.config.query.enabled {
+        let socket = UdpSocket::bind(format!("{host}:{}", server.config.query.port)).await?;
+        tokio::spawn(query::listen(server.clone(), socket, server.config.query.rate_limit));
+    }
 
-            let _: () = redis_pool.get().await.unwrap().set("test", "test").await.unwrap();
+    if server.config.metrics.enabled {
+        let metrics = &server.config.metrics;
+        let metrics_listener = TcpListener::bind(format!("{}:{}", metrics.host, metrics.port)).await?;
+        tokio::spawn(metrics::listen(server.clone(), metrics_listener));
+    }
 
-            if let Err(e) = handle_connection(stream, redis_pool).await {
-                error!("Client: {}. Connection error: {}", addr, e);
+    loop {
+        let (mut stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = server.wait_for_shutdown() => break,
+        };
+        let server = server.clone();
+        let span = info_span!(
+            "connection",
+            peer = %peer,
+            protocol = field::Empty,
+            player = field::Empty,
+        );
+
+        let connection = async move {
+            let proxy_protocol = &server.config.proxy_protocol;
+            let addr = if proxy_protocol.enabled {
+                match proxy_protocol::accept(&mut stream, peer, &proxy_protocol.trusted).await {
+                    Ok(addr) => addr,
+                    Err(e) => {
+                        METRICS.handshake_failed("proxy_protocol");
+                        warn!(error = %e, "PROXY protocol error");
+                        return;
+                    }
+                }
             } else {
-                info!("Close connection with client: {}", addr);
+                peer
+            };
+            if addr != peer {
+                Span::current().record("peer", field::display(addr));
             }
-        });
+
+            info!("Connection opened");
+
+            match handle_connection(stream, addr, server).await {
+                Ok(()) => info!("Connection closed"),
+                Err(e) if e.is::<ConnectionClosed>() => info!("Connection closed by client"),
+                Err(e) => error!(error = %e, "Connection error"),
+            }
+        };
+        tokio::spawn(connection.instrument(span));
+    }
+
+    info!("Stopping the server");
+    // Give connections a moment to deliver their disconnect packets
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    while !server.sessions.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    server.game.stop();
+    if game_loop.join().is_err() {
+        error!("Game loop panicked");
+    }
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, server.game.saves().flush())
+        .await
+        .is_err()
+    {
+        warn!("Timed out saving player data");
     }
+    server.disable_plugins();
+
+    Ok(())
 }
 
-async fn handle_connection(mut stream: TcpStream, redis_pool: Pool) -> Result<()> {
-    let mut conn = ClientConnection::new(&mut stream)?;
+/// Text or JSON logs, written through the console so they don't garble the prompt.
+fn init_tracing(config: &LoggingConfig, writer: Box<dyn Write + Send>) -> Result<()> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(filter) => EnvFilter::try_new(filter)?,
+        Err(_) => EnvFilter::try_new(&config.filter)?,
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(Mutex::new(writer));
+
+    match config.format {
+        LogFormat::Text => subscriber.with_ansi(std::io::stderr().is_terminal()).init(),
+        LogFormat::Json => subscriber.json().with_current_span(false).with_span_list(true).init(),
+    }
+    Ok(())
+}
 
-    let handshake = conn.handshake().await?;
-    info!("{:?}", handshake);
+async fn handle_connection(mut stream: TcpStream, addr: SocketAddr, server: Arc<Server>) -> Result<()> {
+    let mut conn = ClientConnection::new(&mut stream, addr)?;
 
-    {
-        let mut conn = redis_pool.get().await?;
-        let _: () = conn.set("test", "test").await?;
+    let handshake = conn.handshake(server.config.forwarding.mode).await?;
+    let span = Span::current();
+    span.record("protocol", i32::from(handshake.protocol_ver));
+    if conn.addr != addr {
+        span.record("peer", field::display(conn.addr));
     }
+    debug!(host = %handshake.host, port = handshake.port, intent = ?handshake.intent, "Handshake");
 
-    loop {
-        match &conn.state {
+    let result = handle_states(&mut conn, &server).await;
+
+    if let Some(reason) = &conn.disconnect_reason {
+        info!(state = conn.state.label(), reason = %reason, "Disconnected");
+    }
+
+    result
+}
+
+async fn handle_states(conn: &mut ClientConnection<'_>, server: &Server) -> Result<()> {
+    while !conn.is_disconnected() {
+        let state = conn.state;
+        // Each state gets its own span, so the state is logged once per event
+        let span = info_span!("state", state = state.label());
+        handle_state(conn, server, state).instrument(span).await?;
+        if state == ClientState::Status {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the connection until it leaves the state or is disconnected.
+async fn handle_state(conn: &mut ClientConnection<'_>, server: &Server, state: ClientState) -> Result<()> {
+    while !conn.is_disconnected() && conn.state == state {
+        match state {
             ClientState::Status => {
-                handle_status_request(&mut conn).await?;
+                handle_status_request(conn, server).await?;
                 break;
             }
-            ClientState::Login => handle_login_request(&mut conn).await?,
-            ClientState::Configuration => handle_configuration_request(&mut conn).await?,
-            ClientState::Play => handle_play_request(&mut conn).await?,
+            state => match conn.next_incoming().await? {
+                Incoming::Command(command) => conn.handle_session_command(command).await?,
+                Incoming::Request(request) => {
+                    let packet_id = request.packet_id();
+                    let client = match &conn.profile {
+                        Some(profile) => profile.name.clone(),
+                        None => conn.addr.to_string(),
+                    };
+                    let _watch = server.watchdog.enter(format!(
+                        "handler of packet 0x{:02X} in {} from {}",
+                        packet_id,
+                        state.label(),
+                        client
+                    ));
+                    let span = debug_span!("handle", packet_id = %format_args!("0x{:02X}", packet_id));
+                    handle_request(conn, server, state, request).instrument(span).await?
+                }
+            },
         }
     }
 
     Ok(())
 }
 
-async fn handle_status_request(conn: &mut ClientConnection<'_>) -> Result<()> {
+async fn handle_request(
+    conn: &mut ClientConnection<'_>,
+    server: &Server,
+    state: ClientState,
+    request: Request,
+) -> Result<()> {
+    match state {
+        ClientState::Login => handle_login_request(conn, server, request).await,
+        ClientState::Configuration => handle_configuration_request(conn, server, request).await,
+        _ => handle_play_request(conn, server, request).await,
+    }
+}
+
+async fn handle_status_request(conn: &mut ClientConnection<'_>, server: &Server) -> Result<()> {
     'end_status: loop {
         match conn.read_request().await {
             Ok(Request::Status { .. }) => {
                 let response = Response::Status {
-                    cluster_info: SERVER_INFO.into(),
+                    cluster_info: server.status_json().into(),
                 };
                 conn.send_response(response).await?;
             }
@@ -118,83 +342,340 @@ async fn handle_status_request(conn: &mut ClientConnection<'_>) -> Result<()> {
     Ok(())
 }
 
-async fn handle_login_request(conn: &mut ClientConnection<'_>) -> Result<()> {
-    match conn.read_request().await {
-        Ok(Request::LoginStart { username, uuid, .. }) => {
-            info!("Username: {}, UUID: {}", username, uuid);
+async fn handle_login_request(conn: &mut ClientConnection<'_>, server: &Server, request: Request) -> Result<()> {
+    match request {
+        Request::LoginStart { username, uuid, .. } => {
+            info!(%username, %uuid, "Login start");
 
-            if username != "wristylotus" {
-                conn.send_response(Response::LoginDisconnect {
-                    message: r#"{text: "I don't know you, fuck off!", color: "red"}"#.into(),
-                })
-                .await?;
-            } else {
-                conn.send_response(Response::LoginSuccess { uuid, username }).await?;
+            if !is_valid_username(username.as_str()) {
+                METRICS.login_failed("invalid_username");
+                return conn.disconnect(format!("Invalid username: {}", username)).await;
+            }
+
+            match server.config.forwarding.mode {
+                ForwardingMode::Velocity => {
+                    conn.send_response(Response::LoginPluginRequest {
+                        message_id: VELOCITY_MESSAGE_ID.into(),
+                        channel: VELOCITY_CHANNEL.into(),
+                        data: vec![VELOCITY_FORWARDING_VERSION].into(),
+                    })
+                    .await?;
+                }
+                ForwardingMode::Bungeecord => {
+                    let Some(forwarded) = conn.forwarded.as_mut() else {
+                        bail!("BungeeCord forwarding data is missing");
+                    };
+                    forwarded.profile.name = username.to_string();
+                    let profile = forwarded.profile.clone();
+                    complete_login(conn, server, profile).await?;
+                }
+                ForwardingMode::None => {
+                    // The client-supplied UUID is not trusted in offline mode
+                    let profile = GameProfile::new(offline_uuid(username.as_str()), username.as_str());
+                    complete_login(conn, server, profile).await?;
+                }
             }
         }
-        Ok(Request::LoginAcknowledged { .. }) => {
-            info!("Login Acknowledged");
+        Request::LoginPluginResponse { message_id, data, .. }
+            if server.config.forwarding.mode == ForwardingMode::Velocity && message_id == VELOCITY_MESSAGE_ID =>
+        {
+            let Some(data) = data else {
+                METRICS.login_failed("velocity_missing");
+                return conn
+                    .disconnect("This server requires you to connect with Velocity.")
+                    .await;
+            };
+
+            match parse_velocity_player_info(server.config.forwarding.secret.as_bytes(), data) {
+                Ok(forwarded) => {
+                    info!(player = %forwarded.profile.name, addr = %forwarded.addr, "Velocity forwarded player");
+                    Span::current().record(
+                        "peer",
+                        field::display(SocketAddr::new(forwarded.addr, conn.addr.port())),
+                    );
+                    conn.addr = SocketAddr::new(forwarded.addr, conn.addr.port());
+                    let profile = forwarded.profile.clone();
+                    conn.forwarded = Some(forwarded);
+                    complete_login(conn, server, profile).await?;
+                }
+                Err(err) => {
+                    METRICS.login_failed("velocity_invalid");
+                    warn!(error = %err, "Velocity forwarding failed");
+                    conn.disconnect("Unable to verify player details.").await?;
+                }
+            }
+        }
+        Request::LoginAcknowledged { .. } => {
+            debug!("Login acknowledged");
             conn.state = ClientState::Configuration;
+            conn.announce_channels(&server.brand, &server.channels).await?;
         }
-        Ok(req) => bail!("Request '{:?}' not expected in Login state", req),
-        Err(err) => bail!(err),
+        req => bail!("Request '{:?}' not expected in Login state", req),
     }
 
     Ok(())
 }
 
-async fn handle_configuration_request(conn: &mut ClientConnection<'_>) -> Result<()> {
-    match conn.read_request().await {
-        Ok(req @ Request::ClientConfiguration { .. }) => {
-            info!("{:?}", req);
+async fn complete_login(conn: &mut ClientConnection<'_>, server: &Server, profile: GameProfile) -> Result<()> {
+    // Without storage, bans and the whitelist can't be checked and player data can't be saved
+    if !server.storage.is_available() {
+        METRICS.login_failed("storage_unavailable");
+        return conn.disconnect(STORAGE_UNAVAILABLE_MESSAGE).await;
+    }
+    let whitelist = server.config.access.whitelist;
+    match server.access.check_login(&profile, conn.addr.ip(), whitelist).await {
+        Ok(Some(reason)) => {
+            METRICS.login_failed("access_denied");
+            return conn.disconnect(reason).await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            METRICS.login_failed("storage_unavailable");
+            warn!(error = %e, "Failed to check access lists");
+            return conn.disconnect(STORAGE_UNAVAILABLE_MESSAGE).await;
+        }
+    }
+
+    let event = server.fire(PlayerPreLoginEvent {
+        profile: profile.clone(),
+        addr: conn.addr,
+        cancelled: false,
+        kick_message: TextComponent::new("You are not allowed to join this server"),
+    });
+    if event.cancelled {
+        METRICS.login_failed("plugin");
+        return conn.disconnect(event.kick_message).await;
+    }
+
+    // Operators may join a full server
+    let max_players = match server.access.is_op(&profile.name).await? {
+        true => None,
+        false => Some(server.config.gameplay.max_players),
+    };
+    match server
+        .sessions
+        .register(profile.clone(), server.config.login.duplicate_login, max_players)
+    {
+        Ok(session) => {
+            Span::current().record("player", profile.name.as_str());
+            conn.session = Some(session);
+            conn.profile = Some(profile.clone());
+            conn.send_response(Response::LoginSuccess { profile }).await
+        }
+        Err(e) => {
+            METRICS.login_failed(e.label());
+            conn.disconnect(e.message()).await
+        }
+    }
+}
+
+async fn handle_configuration_request(
+    conn: &mut ClientConnection<'_>,
+    server: &Server,
+    request: Request,
+) -> Result<()> {
+    match request {
+        Request::ClientConfiguration { settings, .. } => {
+            debug!(?settings, "Client settings");
+            conn.settings = Some(settings);
 
             // TODO https://minecraft.wiki/w/Java_Edition_protocol/Registry_data
 
-            conn.send_response(Response::ConfigurationFinish).await?;
-        }
-        Ok(req @ Request::PluginMessage { .. }) => {
-            info!("{:?}", req);
-        }
-        Ok(Request::AcknowledgeFinishConfiguration { .. }) => {
-            conn.send_response(Response::LoginPlay {
-                entity_id: 777,
-                is_hardcore: false,
-                dimension_names: vec!["minecraft:overworld".into()],
-                max_players: 2.into(),
-                simulation_distance: 8.into(),
-                reduced_debug_info: false,
-                view_distance: 8.into(),
-                enable_respawn_screen: true,
-                do_limited_crafting: false,
-                dimension_type: 0.into(),
-                dimension_name: "minecraft:overworld".into(),
-                hashed_seed: 0.into(),
-                game_mode: GameMode::Adventure,
-                previous_game_mode: GameMode::Undefined,
-                is_debug: true,
-                is_flat: true,
-                has_death_location: false,
-                death_dimension_name: None,
-                death_location: None,
-                portal_cooldown: 0.into(),
-                sea_level: 63.into(),
-                enforces_secure_chat: false,
+            conn.send_response(Response::FeatureFlags {
+                flags: server.feature_flags.clone(),
             })
             .await?;
+            conn.send_response(Response::UpdateTags {
+                registries: server.tags.registry_tags(),
+            })
+            .await?;
+
+            if let Some(pack) = &server.resource_pack {
+                // Configuration is finished once the client reports the final pack status
+                conn.push_resource_pack(pack).await?;
+            } else {
+                conn.send_response(Response::ConfigurationFinish).await?;
+            }
+        }
+        Request::ResourcePackResponse { uuid, result, .. } => {
+            info!(%uuid, status = ?result, "Resource pack response");
+            conn.resource_packs.insert(uuid, result);
+
+            if let Some(pack) = server.resource_pack.as_ref().filter(|pack| pack.uuid == uuid) {
+                if pack.should_kick(result) {
+                    conn.disconnect("This server requires the resource pack").await?;
+                } else if result.is_final() {
+                    conn.send_response(Response::ConfigurationFinish).await?;
+                }
+            }
+        }
+        Request::PluginMessage { channel, data, .. } => {
+            conn.handle_plugin_message(&server.channels, channel, data).await?;
+        }
+        Request::AcknowledgeFinishConfiguration { .. } => {
+            let Some(profile) = conn.profile.clone() else {
+                bail!("Configuration finished without a logged in player");
+            };
+            let data = match PlayerData::load(server, profile.uuid).await {
+                Ok(data) => data,
+                Err(e) => {
+                    METRICS.login_failed("storage_unavailable");
+                    warn!(error = %e, "Failed to load player data");
+                    return conn.disconnect(STORAGE_UNAVAILABLE_MESSAGE).await;
+                }
+            };
+            let player = server.players.join(profile.clone());
+            let entity_id = player.entity_id;
+            conn.player = Some(player);
+
+            let login = Response::login_play(&server.config, entity_id, &data, conn.settings.as_ref());
+            conn.send_response(login).await?;
 
             //TODO Generate world
             conn.state = ClientState::Play;
+
+            conn.in_game = Some(server.game.join(entity_id, profile, data));
+        }
+        req => bail!("Request '{:?}' not expected in Configuration state", req),
+    }
+
+    Ok(())
+}
+
+async fn handle_play_request(conn: &mut ClientConnection<'_>, server: &Server, request: Request) -> Result<()> {
+    match request {
+        Request::ClientConfiguration { settings, .. } => {
+            let server_view_distance = server.config.gameplay.view_distance as i32;
+            let view_distance = settings.effective_view_distance(server_view_distance);
+            if let Some(previous) = conn.settings.replace(settings.clone()) {
+                let previous_view_distance = previous.effective_view_distance(server_view_distance);
+                if previous_view_distance != view_distance {
+                    info!(from = previous_view_distance, to = view_distance, "View distance changed");
+                    conn.send_response(Response::SetRenderDistance {
+                        view_distance: view_distance.into(),
+                    })
+                    .await?;
+                }
+                if previous.locale != settings.locale {
+                    info!(from = %previous.locale, to = %settings.locale, "Locale changed");
+                }
+                if previous.main_hand != settings.main_hand {
+                    info!(from = ?previous.main_hand, to = ?settings.main_hand, "Main hand changed");
+                }
+            }
+        }
+        Request::PluginMessage { channel, data, .. } => {
+            conn.handle_plugin_message(&server.channels, channel, data).await?;
+        }
+        Request::ResourcePackResponse { uuid, result, .. } => {
+            info!(%uuid, status = ?result, "Resource pack response");
+            conn.resource_packs.insert(uuid, result);
+
+            let pack = server.resource_pack.as_ref().filter(|pack| pack.uuid == uuid);
+            if pack.is_some_and(|pack| pack.should_kick(result)) {
+                conn.disconnect("This server requires the resource pack").await?;
+            }
+        }
+        Request::ConfirmTeleport { teleport_id, .. } => {
+            if !conn.confirm_teleport(teleport_id) {
+                debug!(%teleport_id, "Outdated teleport confirmation");
+            }
+        }
+        Request::PlayerMovement {
+            position,
+            rotation,
+            on_ground,
+            ..
+        } => {
+            // Movement sent before the client saw the latest teleport is relative to the old location
+            if !conn.is_teleporting() {
+                let action = PlayerAction::Move {
+                    position,
+                    rotation,
+                    on_ground,
+                };
+                server.game.queue(entity_id(conn)?, action);
+            }
+        }
+        Request::ChatMessage { message, .. } => {
+            let action = PlayerAction::Chat {
+                message: message.to_string(),
+            };
+            server.game.queue(entity_id(conn)?, action);
+        }
+        Request::ChatCommand { command, .. } => {
+            let profile = player_profile(conn)?;
+            info!(command = %command, "Command");
+            let output = match server.access.is_op(&profile.name).await {
+                Ok(true) => command::execute(server, &CommandSender::Player(profile), command.as_str()).await,
+                Ok(false) => "You do not have permission to use this command".to_string(),
+                Err(e) => {
+                    warn!(error = %e, "Failed to check operator permission");
+                    "Commands are unavailable right now, please try again later".to_string()
+                }
+            };
+            if !output.is_empty() {
+                conn.send_response(Response::SystemChat {
+                    content: TextComponent::new(output),
+                    overlay: false,
+                })
+                .await?;
+            }
         }
-        Ok(req) => bail!("Request '{:?}' not expected in Configuration state", req),
-        Err(err) => bail!(err),
+        Request::PlayerAction {
+            status,
+            position,
+            sequence,
+            ..
+        } => {
+            if matches!(
+                status,
+                PlayerActionStatus::StartedDigging
+                    | PlayerActionStatus::CancelledDigging
+                    | PlayerActionStatus::FinishedDigging
+            ) {
+                let action = PlayerAction::Dig {
+                    status,
+                    position,
+                    sequence,
+                };
+                server.game.queue(entity_id(conn)?, action);
+            }
+        }
+        Request::UseItemOn {
+            hand,
+            position,
+            face,
+            sequence,
+            ..
+        } => {
+            let action = PlayerAction::UseItemOn {
+                hand,
+                position,
+                face,
+                sequence,
+            };
+            server.game.queue(entity_id(conn)?, action);
+        }
+        Request::Unknown { packet_id } => {
+            debug!(packet_id = %format_args!("0x{:02X}", packet_id), "Ignored packet");
+        }
+        req => bail!("Request '{:?}' not expected in Play state", req),
     }
 
     Ok(())
 }
 
-async fn handle_play_request(conn: &mut ClientConnection<'_>) -> Result<()> {
-    match conn.read_request().await {
-        Ok(req) => bail!("Request '{:?}' not expected in Play state", req),
-        Err(err) => bail!(err),
+fn player_profile(conn: &ClientConnection<'_>) -> Result<GameProfile> {
+    match &conn.profile {
+        Some(profile) => Ok(profile.clone()),
+        None => bail!("Play request without a logged in player"),
+    }
+}
+
+fn entity_id(conn: &ClientConnection<'_>) -> Result<i32> {
+    match &conn.player {
+        Some(player) => Ok(player.entity_id),
+        None => bail!("Play request without an entity"),
     }
 }
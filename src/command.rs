@@ -0,0 +1,314 @@
+use crate::access::{BanEntry, BanKind};
+use crate::plugin::CommandEvent;
+use crate::protocol::types::{GameProfile, TextComponent};
+use crate::server::{Server, SessionCommand};
+use anyhow::{anyhow, bail};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+const MAX_PROFILE_SECS: u64 = 300;
+
+const HELP: &str = "\
+list - list online players
+tps - show ticks per second and milliseconds per tick
+profile <seconds> - record per-system tick timings to a report file
+kick <player> [reason] - disconnect a player
+ban <player> [reason] - ban a player by name
+pardon <player> - remove a player ban
+ban-ip <ip> [reason] - ban an IP address
+pardon-ip <ip> - remove an IP ban
+banlist [players|ips] - list active bans
+whitelist <add|remove> <player> - manage the whitelist
+whitelist list - list whitelisted players
+op <player> / deop <player> - manage operators
+plugins [reload [plugin]] - list or reload WebAssembly plugins
+say <message> - broadcast a message
+stop - stop the server";
+
+/// Who issued a command, recorded as the source of bans and in broadcasts.
+#[derive(Debug, Clone)]
+pub enum CommandSender {
+    Console,
+    Rcon(SocketAddr),
+    Player(GameProfile),
+    /// WebAssembly plugin, by name.
+    Plugin(String),
+}
+
+impl CommandSender {
+    pub fn name(&self) -> &str {
+        match self {
+            CommandSender::Console => "Server",
+            CommandSender::Rcon(_) => "Rcon",
+            CommandSender::Player(profile) => &profile.name,
+            CommandSender::Plugin(name) => name,
+        }
+    }
+}
+
+/// Administrative commands shared by the console and RCON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    List,
+    Tps,
+    Profile { seconds: u64 },
+    Kick { player: String, reason: Option<String> },
+    Ban { player: String, reason: Option<String> },
+    Pardon { player: String },
+    BanIp { ip: IpAddr, reason: Option<String> },
+    PardonIp { ip: IpAddr },
+    BanList { kind: BanKind },
+    WhitelistAdd { player: String },
+    WhitelistRemove { player: String },
+    WhitelistList,
+    Op { player: String },
+    Deop { player: String },
+    Say { message: String },
+    Stop,
+}
+
+impl Command {
+    /// Parses a command line, with or without a leading `/`.
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        let line = line.trim();
+        let line = line.strip_prefix('/').unwrap_or(line);
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        let (first, tail) = rest.split_once(' ').unwrap_or((rest, ""));
+        let tail = Some(tail.trim()).filter(|tail| !tail.is_empty()).map(String::from);
+        let player = || -> anyhow::Result<String> {
+            match first {
+                "" => bail!("Usage: {} <player>", name),
+                player => Ok(player.to_string()),
+            }
+        };
+        let ip = || -> anyhow::Result<IpAddr> {
+            first
+                .parse()
+                .map_err(|_| anyhow!("Usage: {} <ip>, '{}' is not an IP address", name, first))
+        };
+
+        Ok(match name.to_lowercase().as_str() {
+            "help" | "?" => Command::Help,
+            "list" => Command::List,
+            "tps" => Command::Tps,
+            "profile" => match first.parse() {
+                Ok(seconds @ 1..=MAX_PROFILE_SECS) => Command::Profile { seconds },
+                _ => bail!("Usage: profile <seconds>, between 1 and {}", MAX_PROFILE_SECS),
+            },
+            "kick" => Command::Kick {
+                player: player()?,
+                reason: tail,
+            },
+            "ban" => Command::Ban {
+                player: player()?,
+                reason: tail,
+            },
+            "pardon" => Command::Pardon { player: player()? },
+            "ban-ip" => Command::BanIp {
+                ip: ip()?,
+                reason: tail,
+            },
+            "pardon-ip" => Command::PardonIp { ip: ip()? },
+            "banlist" => match first {
+                "" | "players" => Command::BanList { kind: BanKind::Name },
+                "ips" => Command::BanList { kind: BanKind::Ip },
+                _ => bail!("Usage: banlist [players|ips]"),
+            },
+            "whitelist" => match (first, tail) {
+                ("list", _) => Command::WhitelistList,
+                ("add", Some(player)) => Command::WhitelistAdd { player },
+                ("remove", Some(player)) => Command::WhitelistRemove { player },
+                _ => bail!("Usage: whitelist <add|remove|list> [player]"),
+            },
+            "op" => Command::Op { player: player()? },
+            "deop" => Command::Deop { player: player()? },
+            "say" if !rest.is_empty() => Command::Say {
+                message: rest.to_string(),
+            },
+            "say" => bail!("Usage: say <message>"),
+            "stop" => Command::Stop,
+            "" => bail!("Empty command"),
+            _ => bail!("Unknown command '{}', type 'help' for a list of commands", name),
+        })
+    }
+
+    /// Runs the command and returns its output, one line per message.
+    pub async fn execute(self, server: &Server, sender: &CommandSender) -> anyhow::Result<String> {
+        match self {
+            Command::Help => Ok(HELP.to_string()),
+            Command::List => {
+                let mut names: Vec<String> = server
+                    .players
+                    .all()
+                    .into_iter()
+                    .map(|player| player.profile.name)
+                    .collect();
+                names.sort_unstable_by_key(|name| name.to_lowercase());
+                Ok(format!(
+                    "There are {} of a max of {} players online: {}",
+                    names.len(),
+                    server.config.gameplay.max_players,
+                    names.join(", ")
+                ))
+            }
+            Command::Tps => {
+                let timings = server.game.timings();
+                Ok(format!(
+                    "TPS: {:.1}, MSPT: {:.2} ms (peak {:.2} ms), {} ticks skipped",
+                    timings.tps(),
+                    timings.mspt(),
+                    timings.peak_mspt(),
+                    timings.skipped
+                ))
+            }
+            Command::Profile { seconds } => {
+                let requester = match sender {
+                    CommandSender::Player(profile) => Some(profile.uuid),
+                    _ => None,
+                };
+                server.profiler.start(Duration::from_secs(seconds), requester)?;
+                Ok(format!(
+                    "Profiling for {} seconds, the report will be written to {}",
+                    seconds,
+                    server.config.diagnostics.profile_directory.display()
+                ))
+            }
+            Command::Kick { player, reason } => {
+                let reason = reason.unwrap_or_else(|| "Kicked by an operator".into());
+                if kick(server, &player, TextComponent::new(reason.as_str())) {
+                    Ok(format!("Kicked {}: {}", player, reason))
+                } else {
+                    bail!("Player {} is not online", player)
+                }
+            }
+            Command::Ban { player, reason } => {
+                let entry = BanEntry::new(&player, reason.unwrap_or_default(), sender.name());
+                let message = entry.message();
+                server.access.ban_add(BanKind::Name, entry).await?;
+                kick(server, &player, message);
+                Ok(format!("Banned {}", player))
+            }
+            Command::Pardon { player } => match server.access.ban_remove(BanKind::Name, &player).await? {
+                true => Ok(format!("Unbanned {}", player)),
+                false => bail!("{} is not banned", player),
+            },
+            Command::BanIp { ip, reason } => {
+                let entry = BanEntry::new(ip.to_string(), reason.unwrap_or_default(), sender.name());
+                server.access.ban_add(BanKind::Ip, entry).await?;
+                Ok(format!("Banned IP {}", ip))
+            }
+            Command::PardonIp { ip } => match server.access.ban_remove(BanKind::Ip, &ip.to_string()).await? {
+                true => Ok(format!("Unbanned IP {}", ip)),
+                false => bail!("IP {} is not banned", ip),
+            },
+            Command::BanList { kind } => {
+                let bans = server.access.bans(kind).await?;
+                let mut output = format!("There are {} ban(s):", bans.len());
+                for ban in bans {
+                    output.push_str(&format!(
+                        "\n{} was banned by {}: {}",
+                        ban.target, ban.source, ban.reason
+                    ));
+                }
+                Ok(output)
+            }
+            Command::WhitelistAdd { player } => match server.access.whitelist_add(&player).await? {
+                true => Ok(format!("Added {} to the whitelist", player)),
+                false => bail!("{} is already whitelisted", player),
+            },
+            Command::WhitelistRemove { player } => match server.access.whitelist_remove(&player).await? {
+                true => Ok(format!("Removed {} from the whitelist", player)),
+                false => bail!("{} is not whitelisted", player),
+            },
+            Command::WhitelistList => {
+                let mut names = server.access.whitelist().await?;
+                names.sort_unstable();
+                Ok(format!(
+                    "There are {} whitelisted player(s): {}",
+                    names.len(),
+                    names.join(", ")
+                ))
+            }
+            Command::Op { player } => match server.access.op_add(&player).await? {
+                true => Ok(format!("Made {} a server operator", player)),
+                false => bail!("{} is already an operator", player),
+            },
+            Command::Deop { player } => match server.access.op_remove(&player).await? {
+                true => Ok(format!("Made {} no longer a server operator", player)),
+                false => bail!("{} is not an operator", player),
+            },
+            Command::Say { message } => {
+                let message = format!("[{}] {}", sender.name(), message);
+                let content = TextComponent::new(message.as_str());
+                server.sessions.broadcast(|| SessionCommand::Message(content.clone()));
+                Ok(message)
+            }
+            Command::Stop => {
+                server.shutdown();
+                Ok("Stopping the server".into())
+            }
+        }
+    }
+}
+
+/// Parses and runs a command line, formatting errors as output.
+///
+/// Plugins see the line first and may run it themselves by cancelling the `CommandEvent`.
+pub async fn execute(server: &Server, sender: &CommandSender, line: &str) -> String {
+    let event = server.fire(CommandEvent {
+        sender: sender.clone(),
+        line: line.trim().to_string(),
+        cancelled: false,
+        response: None,
+    });
+    if event.cancelled {
+        return event.response.unwrap_or_default();
+    }
+
+    match Command::parse(&event.line) {
+        Ok(command) => command.execute(server, sender).await.unwrap_or_else(|e| e.to_string()),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn kick(server: &Server, name: &str, reason: TextComponent) -> bool {
+    server
+        .sessions
+        .find_by_name(name)
+        .is_some_and(|profile| server.sessions.send(&profile.uuid, SessionCommand::Disconnect(reason)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            Command::parse("/kick Notch griefing the spawn").unwrap(),
+            Command::Kick {
+                player: "Notch".into(),
+                reason: Some("griefing the spawn".into())
+            }
+        );
+        assert_eq!(
+            Command::parse("whitelist add jeb_").unwrap(),
+            Command::WhitelistAdd { player: "jeb_".into() }
+        );
+        assert_eq!(
+            Command::parse("ban-ip 192.0.2.1").unwrap(),
+            Command::BanIp {
+                ip: "192.0.2.1".parse().unwrap(),
+                reason: None
+            }
+        );
+        assert_eq!(Command::parse("profile 30").unwrap(), Command::Profile { seconds: 30 });
+        assert!(Command::parse("profile 0").is_err());
+        assert!(Command::parse("ban-ip Notch").is_err());
+        assert!(Command::parse("kick").is_err());
+        assert!(Command::parse("fly").is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use crate::connection::forwarding::ForwardingMode;
+use crate::connection::resource_pack::ResourcePack;
+use crate::protocol::types::enums::GameMode;
+use crate::server::DuplicateLoginPolicy;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file '{path}': {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("Failed to parse config file '{path}': {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("Invalid config value '{field}': {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+/// Server settings loaded from a TOML file, see `--print-default-config`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub network: NetworkConfig,
+    pub proxy_protocol: ProxyProtocolConfig,
+    pub rcon: RconConfig,
+    pub query: QueryConfig,
+    pub metrics: MetricsConfig,
+    pub logging: LoggingConfig,
+    pub plugins: PluginsConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub motd: MotdConfig,
+    pub login: LoginConfig,
+    pub forwarding: ForwardingConfig,
+    pub gameplay: GameplayConfig,
+    pub world: WorldConfig,
+    pub access: AccessConfig,
+    pub storage: StorageConfig,
+    pub redis: RedisConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_pack: Option<ResourcePackConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyProtocolConfig {
+    /// Expect a PROXY protocol v1/v2 header before the handshake on every connection.
+    pub enabled: bool,
+    /// Networks allowed to send the header, required when enabled.
+    pub trusted: Vec<IpNet>,
+}
+
+/// Remote console, bound on `network.host`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RconConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub password: String,
+}
+
+/// GameSpy4 UDP query, bound on `network.host`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Packets accepted per second from a single address.
+    pub rate_limit: u32,
+}
+
+/// Prometheus `/metrics` endpoint, local only by default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Default filter directives, `RUST_LOG` takes precedence.
+    pub filter: String,
+}
+
+/// Sandboxed WebAssembly plugins, loaded from `*.wasm` files in `directory`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    pub enabled: bool,
+    pub directory: PathBuf,
+    /// Fuel a plugin may burn per call, roughly one unit per instruction.
+    pub fuel: u64,
+    /// Limit of the linear memory of each plugin, in MiB.
+    pub max_memory_mb: usize,
+    /// Reload plugins when their file changes.
+    pub watch: bool,
+}
+
+/// Watchdog for slow ticks and packet handlers, and reports of the `profile` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagnosticsConfig {
+    pub watchdog: bool,
+    /// Ticks, handlers and plugin calls running longer than this are reported.
+    pub slow_threshold_ms: u64,
+    pub profile_directory: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line, for log aggregation.
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MotdConfig {
+    pub text: String,
+    /// 64x64 PNG shown in the server list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginConfig {
+    pub duplicate_login: DuplicateLoginPolicy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ForwardingConfig {
+    pub mode: ForwardingMode,
+    /// Shared secret configured in the proxy, required for Velocity forwarding.
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameplayConfig {
+    pub max_players: u32,
+    pub game_mode: GameMode,
+    pub hardcore: bool,
+    pub view_distance: u8,
+    pub simulation_distance: u8,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub enforce_secure_chat: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldConfig {
+    pub dimension: String,
+    pub sea_level: i32,
+    pub flat: bool,
+    pub debug: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessConfig {
+    /// Only whitelisted players and operators may join.
+    pub whitelist: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Seconds between saves of the online players, 0 to only save them when they leave.
+    pub autosave_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Redis at `redis.url`.
+    #[default]
+    Redis,
+    /// Kept in memory and lost on restart, for development.
+    Memory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConfig {
+    pub url: String,
+    pub pool_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourcePackConfig {
+    pub url: String,
+    #[serde(default)]
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub forced: bool,
+    #[serde(default)]
+    pub kick_on_decline: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".into(),
+            port: 25565,
+        }
+    }
+}
+
+impl Default for RconConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25575,
+            password: String::new(),
+        }
+    }
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25565,
+            rate_limit: 10,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".into(),
+            port: 9225,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            filter: "info".into(),
+        }
+    }
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: "plugins".into(),
+            fuel: 10_000_000,
+            max_memory_mb: 16,
+            watch: true,
+        }
+    }
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            watchdog: true,
+            slow_threshold_ms: 1000,
+            profile_directory: "profiles".into(),
+        }
+    }
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "redis" => Ok(StorageBackend::Redis),
+            "memory" => Ok(StorageBackend::Memory),
+            _ => Err(format!("unknown storage backend '{}', expected redis or memory", value)),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{}', expected text or json", value)),
+        }
+    }
+}
+
+impl Default for MotdConfig {
+    fn default() -> Self {
+        Self {
+            text: "Rust Minecraft Server".into(),
+            favicon: None,
+        }
+    }
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        Self {
+            max_players: 2,
+            game_mode: GameMode::Adventure,
+            hardcore: false,
+            view_distance: 8,
+            simulation_distance: 8,
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            enforce_secure_chat: false,
+        }
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            dimension: "minecraft:overworld".into(),
+            sea_level: 63,
+            flat: true,
+            debug: true,
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            autosave_interval_secs: 300,
+        }
+    }
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://localhost:6379".into(),
+            pool_size: 10,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.into(),
+            source,
+        })?;
+
+        toml::from_str(&content).map_err(|source| ConfigError::Parse {
+            path: path.into(),
+            source,
+        })
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("Server config is always serializable")
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        fn invalid(field: &'static str, reason: impl Into<String>) -> Result<(), ConfigError> {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.into(),
+            })
+        }
+
+        if self.network.port == 0 {
+            return invalid("network.port", "must not be 0");
+        }
+        if self.proxy_protocol.enabled && self.proxy_protocol.trusted.is_empty() {
+            return invalid("proxy_protocol.trusted", "must list the proxy networks when PROXY protocol is enabled");
+        }
+        if self.rcon.enabled && self.rcon.password.is_empty() {
+            return invalid("rcon.password", "must be set when RCON is enabled");
+        }
+        if self.rcon.enabled && self.rcon.port == self.network.port {
+            return invalid("rcon.port", "must differ from network.port");
+        }
+        if self.query.enabled && self.query.port == 0 {
+            return invalid("query.port", "must not be 0");
+        }
+        if self.query.enabled && self.query.rate_limit == 0 {
+            return invalid("query.rate_limit", "must be positive");
+        }
+        if self.plugins.enabled && self.plugins.fuel == 0 {
+            return invalid("plugins.fuel", "must be positive");
+        }
+        if self.plugins.enabled && self.plugins.max_memory_mb == 0 {
+            return invalid("plugins.max_memory_mb", "must be positive");
+        }
+        if self.diagnostics.slow_threshold_ms == 0 {
+            return invalid("diagnostics.slow_threshold_ms", "must be positive");
+        }
+        if self.forwarding.mode == ForwardingMode::Velocity && self.forwarding.secret.is_empty() {
+            return invalid("forwarding.secret", "must be set for Velocity forwarding");
+        }
+        if self.gameplay.max_players == 0 || self.gameplay.max_players > i32::MAX as u32 {
+            return invalid(
+                "gameplay.max_players",
+                format!("must be positive, got {}", self.gameplay.max_players),
+            );
+        }
+        if self.gameplay.game_mode == GameMode::Undefined {
+            return invalid(
+                "gameplay.game_mode",
+                "must be survival, creative, adventure or spectator",
+            );
+        }
+        if !(2..=32).contains(&self.gameplay.view_distance) {
+            return invalid(
+                "gameplay.view_distance",
+                format!("must be between 2 and 32, got {}", self.gameplay.view_distance),
+            );
+        }
+        if !(2..=32).contains(&self.gameplay.simulation_distance) {
+            return invalid(
+                "gameplay.simulation_distance",
+                format!("must be between 2 and 32, got {}", self.gameplay.simulation_distance),
+            );
+        }
+        if !self.world.dimension.contains(':') {
+            return invalid(
+                "world.dimension",
+                format!("must be a namespaced identifier, got '{}'", self.world.dimension),
+            );
+        }
+        if self.storage.backend == StorageBackend::Redis && self.redis.pool_size == 0 {
+            return invalid("redis.pool_size", "must not be 0");
+        }
+        if let Some(Err(err)) = self.resource_pack.as_ref().map(ResourcePackConfig::to_resource_pack) {
+            return invalid("resource_pack.hash", err.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl ResourcePackConfig {
+    pub fn to_resource_pack(&self) -> anyhow::Result<ResourcePack> {
+        let mut pack = ResourcePack::new(self.url.clone(), self.hash.clone())?;
+        pack.prompt = self.prompt.clone();
+        pack.forced = self.forced;
+        pack.kick_on_decline = self.kick_on_decline;
+        Ok(pack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_roundtrip() {
+        let config = ServerConfig::default();
+
+        let parsed: ServerConfig = toml::from_str(&config.to_toml()).unwrap();
+
+        assert_eq!(config, parsed);
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_field() {
+        let config: ServerConfig = toml::from_str("[gameplay]\nview_distance = 40").unwrap();
+
+        let err = config.validate().unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Invalid config value 'gameplay.view_distance': must be between 2 and 32, got 40"
+        );
+    }
+
+    #[test]
+    fn test_proxy_protocol_requires_trusted_networks() {
+        let mut config: ServerConfig = toml::from_str("[proxy_protocol]\nenabled = true").unwrap();
+
+        let err = config.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid config value 'proxy_protocol.trusted': must list the proxy networks when PROXY protocol is enabled"
+        );
+
+        config.proxy_protocol.trusted = vec!["10.0.0.0/8".parse().unwrap()];
+        assert!(config.validate().is_ok());
+    }
+}
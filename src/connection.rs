@@ -1,10 +1,21 @@
-use crate::protocol::types::enums::ClientState;
-use crate::protocol::types::{MCString, VarInt};
+use crate::metrics::{HANDSHAKE_STATE, METRICS};
+use crate::protocol::types::enums::{ClientState, Intent, ResourcePackStatus};
+use crate::protocol::types::{ClientSettings, GameProfile, Identifier, Location, MCString, TextComponent, VarInt};
 use crate::protocol::{ProtocolReader, ProtocolWriter};
+use crate::game::JoinedGame;
+use crate::server::{JoinedPlayer, Session, SessionCommand};
 use anyhow::bail;
+use forwarding::{parse_bungeecord_host, ForwardedPlayer, ForwardingMode};
+use response::{Response, SendResponse};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use tokio::net::TcpStream;
+use uuid::Uuid;
 
+pub mod channels;
+pub mod forwarding;
 pub mod request;
+pub mod resource_pack;
 pub mod response;
 
 #[derive(Debug)]
@@ -13,42 +24,205 @@ pub struct Handshake {
     pub protocol_ver: VarInt,
     pub host: MCString,
     pub port: u16,
-    pub state: ClientState,
+    pub intent: Intent,
 }
 
 pub struct ClientConnection<'a> {
     pub state: ClientState,
+    /// Address of the client.
+    pub addr: SocketAddr,
+    /// Real identity of the player forwarded by a proxy.
+    pub forwarded: Option<ForwardedPlayer>,
+    /// Player identity, known after a successful login.
+    pub profile: Option<GameProfile>,
+    /// Registration in the server's session registry, set on `Login Success`.
+    pub session: Option<Session>,
+    /// Player in the registry with their entity ID, set on entering Play.
+    pub player: Option<JoinedPlayer>,
+    /// Player in the game loop, set once their `Join` is queued.
+    pub in_game: Option<JoinedGame>,
+    /// Settings from the latest `Client Information`, updated mid-session in Play.
+    pub settings: Option<ClientSettings>,
+    /// Brand the client reported on `minecraft:brand`.
+    pub brand: Option<String>,
+    /// Plugin channels the client registered with `minecraft:register`.
+    pub channels: HashSet<Identifier>,
+    /// Last status the client reported for each resource pack.
+    pub resource_packs: HashMap<Uuid, ResourcePackStatus>,
+    /// Reason of the server-side disconnect, set once the connection is closed.
+    pub disconnect_reason: Option<TextComponent>,
+    /// Teleport the client has not confirmed yet, its movement is ignored until then.
+    pending_teleport: Option<i32>,
+    next_teleport_id: i32,
+    /// State label the connection is counted under in the connections gauge.
+    metrics_state: &'static str,
     reader: ProtocolReader<'a>,
     writer: ProtocolWriter<'a>,
 }
 
 impl<'a> ClientConnection<'a> {
-    pub fn new(stream: &'a mut TcpStream) -> anyhow::Result<Self> {
+    /// `addr` is the peer address, or the client address from a PROXY protocol header.
+    pub fn new(stream: &'a mut TcpStream, addr: SocketAddr) -> anyhow::Result<Self> {
         let (reader, writer) = stream.split();
+        METRICS.connections.with_label_values(&[HANDSHAKE_STATE]).inc();
 
         Ok(Self {
             state: ClientState::Status,
+            addr,
+            forwarded: None,
+            profile: None,
+            session: None,
+            player: None,
+            in_game: None,
+            settings: None,
+            brand: None,
+            channels: HashSet::new(),
+            resource_packs: HashMap::new(),
+            disconnect_reason: None,
+            pending_teleport: None,
+            next_teleport_id: 0,
+            metrics_state: HANDSHAKE_STATE,
             reader: ProtocolReader::from_stream(reader)?,
             writer: ProtocolWriter::from_stream(writer)?,
         })
     }
 
-    pub async fn handshake(&mut self) -> anyhow::Result<Handshake> {
-        let packet_id = self.reader.packet_id().await?;
+    pub async fn handshake(&mut self, forwarding: ForwardingMode) -> anyhow::Result<Handshake> {
+        let (packet_id, length) = self.reader.packet_header().await?;
+        METRICS.packet_received(HANDSHAKE_STATE, packet_id, length);
 
         if packet_id == 0x00 {
-            let handshake = Handshake {
+            let mut handshake = Handshake {
                 id: packet_id,
                 protocol_ver: self.reader.read_varint().await?,
                 host: self.reader.read_string().await?,
                 port: self.reader.read_u16().await?,
-                state: self.reader.read_varint().await?.into(),
+                intent: self
+                    .reader
+                    .read_varint()
+                    .await?
+                    .try_into()
+                    .inspect_err(|_| METRICS.handshake_failed("unknown_intent"))?,
             };
-            self.state = handshake.state;
+            self.state = handshake.intent.state();
+
+            if forwarding == ForwardingMode::Bungeecord && self.state == ClientState::Login {
+                match parse_bungeecord_host(handshake.host.as_str()) {
+                    Ok((host, forwarded)) => {
+                        handshake.host = host.into();
+                        self.addr = SocketAddr::new(forwarded.addr, self.addr.port());
+                        self.forwarded = Some(forwarded);
+                    }
+                    Err(err) => {
+                        METRICS.handshake_failed("bungeecord_forwarding");
+                        self.disconnect(
+                            "If you wish to use IP forwarding, please enable it in your BungeeCord config as well!",
+                        )
+                        .await?;
+                        bail!("Rejected connection without BungeeCord forwarding: {}", err);
+                    }
+                }
+            }
 
             Ok(handshake)
         } else {
+            METRICS.handshake_failed("unexpected_packet");
             bail!("Unexpected packet ID 0x{:02X}", packet_id);
         }
     }
+
+    /// Sends the disconnect packet of the current state and closes the socket.
+    pub async fn disconnect(&mut self, reason: impl Into<TextComponent>) -> anyhow::Result<()> {
+        let reason = reason.into();
+
+        let response = match self.state {
+            ClientState::Status => None,
+            ClientState::Login => Some(Response::LoginDisconnect {
+                message: reason.to_json().to_string().into(),
+            }),
+            ClientState::Configuration => Some(Response::ConfigurationDisconnect {
+                message: reason.clone(),
+            }),
+            ClientState::Play => Some(Response::PlayDisconnect {
+                message: reason.clone(),
+            }),
+        };
+        self.disconnect_reason = Some(reason);
+
+        if let Some(response) = response {
+            self.send_response(response).await?;
+        }
+        self.writer.shutdown().await
+    }
+
+    pub async fn handle_session_command(&mut self, command: SessionCommand) -> anyhow::Result<()> {
+        match command {
+            SessionCommand::Disconnect(reason) => self.disconnect(reason).await,
+            SessionCommand::Message(content) if self.state == ClientState::Play => {
+                self.send_response(Response::SystemChat {
+                    content,
+                    overlay: false,
+                })
+                .await
+            }
+            SessionCommand::ActionBar(content) if self.state == ClientState::Play => {
+                self.send_response(Response::SystemChat { content, overlay: true })
+                    .await
+            }
+            SessionCommand::Teleport(location) if self.state == ClientState::Play => self.teleport(location).await,
+            SessionCommand::AcknowledgeBlockChange(sequence) if self.state == ClientState::Play => {
+                self.send_response(Response::AcknowledgeBlockChange { sequence }).await
+            }
+            SessionCommand::Message(_)
+            | SessionCommand::ActionBar(_)
+            | SessionCommand::Teleport(_)
+            | SessionCommand::AcknowledgeBlockChange(_) => Ok(()),
+        }
+    }
+
+    /// Moves the player, who must confirm the teleport before their movement is accepted again.
+    pub async fn teleport(&mut self, location: Location) -> anyhow::Result<()> {
+        let teleport_id = self.next_teleport_id;
+        self.next_teleport_id = self.next_teleport_id.wrapping_add(1);
+        self.pending_teleport = Some(teleport_id);
+
+        self.send_response(Response::SynchronizePlayerPosition {
+            teleport_id: teleport_id.into(),
+            location,
+        })
+        .await
+    }
+
+    /// Returns whether the confirmation matches the latest teleport.
+    pub fn confirm_teleport(&mut self, teleport_id: VarInt) -> bool {
+        let confirmed = self.pending_teleport == Some(teleport_id.into());
+        if confirmed {
+            self.pending_teleport = None;
+        }
+        confirmed
+    }
+
+    pub fn is_teleporting(&self) -> bool {
+        self.pending_teleport.is_some()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnect_reason.is_some()
+    }
+
+    /// Moves the connection to the gauge of its current state.
+    fn track_state(&mut self) {
+        let state = self.state.label();
+        if state != self.metrics_state {
+            METRICS.connections.with_label_values(&[self.metrics_state]).dec();
+            METRICS.connections.with_label_values(&[state]).inc();
+            self.metrics_state = state;
+        }
+    }
+}
+
+impl Drop for ClientConnection<'_> {
+    fn drop(&mut self) {
+        METRICS.connections.with_label_values(&[self.metrics_state]).dec();
+    }
 }
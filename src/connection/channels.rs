@@ -0,0 +1,104 @@
+use super::response::{Response, SendResponse};
+use super::ClientConnection;
+use crate::protocol::types::{Identifier, MCString, ReadBuffer, WriteBuffer};
+use bytes::{Bytes, BytesMut};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+pub const BRAND: &str = "minecraft:brand";
+pub const REGISTER: &str = "minecraft:register";
+pub const UNREGISTER: &str = "minecraft:unregister";
+
+/// Handles a plugin message received on a channel. A returned payload is sent back on the same channel.
+pub type ChannelHandler = dyn Fn(&mut ClientConnection<'_>, Bytes) -> anyhow::Result<Option<Bytes>> + Send + Sync;
+
+/// Plugin channels the server listens on, keyed by channel identifier.
+#[derive(Default)]
+pub struct ChannelRegistry {
+    handlers: RwLock<HashMap<Identifier, Arc<ChannelHandler>>>,
+}
+
+impl ChannelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(&self, channel: impl Into<Identifier>, handler: F)
+    where
+        F: Fn(&mut ClientConnection<'_>, Bytes) -> anyhow::Result<Option<Bytes>> + Send + Sync + 'static,
+    {
+        self.handlers.write().unwrap().insert(channel.into(), Arc::new(handler));
+    }
+
+    pub fn unregister(&self, channel: &Identifier) -> bool {
+        self.handlers.write().unwrap().remove(channel).is_some()
+    }
+
+    /// Channels announced to the client with `minecraft:register`.
+    pub fn channels(&self) -> Vec<Identifier> {
+        self.handlers.read().unwrap().keys().cloned().collect()
+    }
+
+    fn handler(&self, channel: &Identifier) -> Option<Arc<ChannelHandler>> {
+        self.handlers.read().unwrap().get(channel).cloned()
+    }
+}
+
+impl ClientConnection<'_> {
+    pub async fn send_plugin_message(&mut self, channel: impl Into<Identifier>, data: Bytes) -> anyhow::Result<()> {
+        self.send_response(Response::PluginMessage {
+            channel: channel.into(),
+            data,
+        })
+        .await
+    }
+
+    /// Sends the server brand and the channels registered on the server.
+    pub async fn announce_channels(&mut self, brand: &str, channels: &ChannelRegistry) -> anyhow::Result<()> {
+        let mut data = BytesMut::new();
+        MCString::from(brand.to_string()).write(&mut data)?;
+        self.send_plugin_message(BRAND, data.freeze()).await?;
+
+        let channels = channels.channels();
+        if !channels.is_empty() {
+            let names: Vec<String> = channels.into_iter().map(Into::into).collect();
+            self.send_plugin_message(REGISTER, names.join("\0").into()).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn handle_plugin_message(
+        &mut self,
+        channels: &ChannelRegistry,
+        channel: Identifier,
+        mut data: Bytes,
+    ) -> anyhow::Result<()> {
+        if channel == BRAND {
+            let brand = MCString::read(&mut data)?;
+            debug!(%brand, "Client brand");
+            self.brand = Some(brand.into());
+        } else if channel == REGISTER {
+            self.channels.extend(parse_channel_names(&data));
+        } else if channel == UNREGISTER {
+            for name in parse_channel_names(&data) {
+                self.channels.remove(&name);
+            }
+        } else if let Some(handler) = channels.handler(&channel) {
+            if let Some(reply) = handler(self, data)? {
+                self.send_plugin_message(channel, reply).await?;
+            }
+        } else {
+            debug!(%channel, "Ignore plugin message on unknown channel");
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_channel_names(data: &[u8]) -> impl Iterator<Item = Identifier> + '_ {
+    data.split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned().into())
+}
@@ -0,0 +1,167 @@
+use crate::protocol::types::{GameProfile, MCString, ProfileProperty, ReadBuffer, VarInt};
+use anyhow::bail;
+use bytes::{Buf, Bytes};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// Highest forwarding version the server understands, `MODERN_DEFAULT`.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+const SIGNATURE_LENGTH: usize = 32;
+
+/// How a proxy in front of the server forwards the real player identity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardingMode {
+    #[default]
+    None,
+    Velocity,
+    /// Legacy BungeeCord IP forwarding through the handshake host.
+    Bungeecord,
+}
+
+/// Player identity forwarded by a proxy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardedPlayer {
+    pub addr: IpAddr,
+    pub profile: GameProfile,
+}
+
+#[derive(Deserialize)]
+struct BungeecordProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+/// Splits a BungeeCord handshake host `<host>\0<ip>\0<uuid>[\0<properties json>]` into the real host
+/// and the forwarded player. The player name is not forwarded and stays empty until `Login Start`.
+pub fn parse_bungeecord_host(host: &str) -> anyhow::Result<(String, ForwardedPlayer)> {
+    let parts: Vec<&str> = host.split('\0').collect();
+    let [host, addr, uuid, rest @ ..] = parts.as_slice() else {
+        bail!("Handshake host has no forwarded data");
+    };
+
+    let properties = match rest {
+        [] => Vec::new(),
+        [properties] => serde_json::from_str::<Vec<BungeecordProperty>>(properties)?
+            .into_iter()
+            .map(|property| ProfileProperty::new(property.name, property.value, property.signature))
+            .collect(),
+        _ => bail!("Handshake host has unexpected forwarded data"),
+    };
+
+    let forwarded = ForwardedPlayer {
+        addr: addr.parse()?,
+        profile: GameProfile {
+            uuid: Uuid::parse_str(uuid)?,
+            name: String::new(),
+            properties,
+        },
+    };
+
+    Ok((host.to_string(), forwarded))
+}
+
+/// Verifies and parses a Velocity `velocity:player_info` login plugin response.
+pub fn parse_velocity_player_info(secret: &[u8], mut data: Bytes) -> anyhow::Result<ForwardedPlayer> {
+    if data.len() < SIGNATURE_LENGTH {
+        bail!("Velocity forwarding data is too short");
+    }
+    let signature = data.split_to(SIGNATURE_LENGTH);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)?;
+    mac.update(&data);
+    if mac.verify_slice(&signature).is_err() {
+        bail!("Velocity forwarding signature mismatch, check the forwarding secret");
+    }
+
+    let version: i32 = VarInt::read(&mut data)?.into();
+    if version < VELOCITY_FORWARDING_VERSION as i32 {
+        bail!("Unsupported Velocity forwarding version {}", version);
+    }
+
+    let addr = MCString::read(&mut data)?.as_str().parse()?;
+    let uuid = Uuid::read(&mut data)?;
+    let name = MCString::read(&mut data)?;
+    let properties = Vec::<ProfileProperty>::read(&mut data)?;
+    if data.has_remaining() {
+        // Newer versions append the chat session key, which is not used
+        data.advance(data.remaining());
+    }
+
+    Ok(ForwardedPlayer {
+        addr,
+        profile: GameProfile {
+            uuid,
+            name: name.into(),
+            properties,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::WriteBuffer;
+    use bytes::{BufMut, BytesMut};
+
+    fn signed(secret: &[u8], payload: &[u8]) -> Bytes {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(payload);
+
+        let mut data = BytesMut::new();
+        data.put_slice(&mac.finalize().into_bytes());
+        data.put_slice(payload);
+        data.freeze()
+    }
+
+    #[test]
+    fn test_parse_bungeecord_host() {
+        let host = [
+            "mc.example.com",
+            "198.51.100.4",
+            "069a79f444e94726a5befca90e38aaf5",
+            r#"[{"name":"textures","value":"abc"}]"#,
+        ]
+        .join("\0");
+
+        let (host, player) = parse_bungeecord_host(&host).unwrap();
+
+        assert_eq!(host, "mc.example.com");
+        assert_eq!(player.addr.to_string(), "198.51.100.4");
+        assert_eq!(player.profile.uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(
+            player.profile.properties,
+            vec![ProfileProperty::new("textures", "abc", None)]
+        );
+
+        assert!(parse_bungeecord_host("mc.example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_velocity_player_info() {
+        let uuid = Uuid::new_v4();
+        let mut payload = BytesMut::new();
+        VarInt::new(1).write(&mut payload).unwrap();
+        MCString::from("203.0.113.7").write(&mut payload).unwrap();
+        uuid.write(&mut payload).unwrap();
+        MCString::from("Steve").write(&mut payload).unwrap();
+        vec![ProfileProperty::new("textures", "abc", Some("sig".into()))]
+            .write(&mut payload)
+            .unwrap();
+
+        let player = parse_velocity_player_info(b"secret", signed(b"secret", &payload)).unwrap();
+
+        assert_eq!(player.addr.to_string(), "203.0.113.7");
+        assert_eq!(player.profile.uuid, uuid);
+        assert_eq!(player.profile.name, "Steve");
+        assert_eq!(player.profile.properties[0].name, "textures");
+
+        assert!(parse_velocity_player_info(b"wrong", signed(b"secret", &payload)).is_err());
+    }
+}
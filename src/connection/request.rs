@@ -1,7 +1,12 @@
 use super::{ClientConnection, ClientState};
-use crate::protocol::types::enums::{ChatMode, Hand, ParticleStatus};
-use crate::protocol::types::{MCString, VarInt};
+use crate::metrics::METRICS;
+use crate::protocol::types::enums::{BlockFace, InteractionHand, PlayerActionStatus, ResourcePackStatus};
+use crate::protocol::types::{ClientSettings, Identifier, MCString, Position, VarInt};
+use crate::protocol::ProtocolReader;
+use crate::server::SessionCommand;
 use anyhow::bail;
+use bytes::Bytes;
+use tracing::{debug_span, Instrument};
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -18,29 +23,93 @@ pub enum Request {
         username: MCString,
         uuid: Uuid,
     },
+    LoginPluginResponse {
+        packet_id: VarInt,
+        message_id: VarInt,
+        data: Option<Bytes>,
+    },
     LoginAcknowledged {
         packet_id: VarInt,
     },
     ClientConfiguration {
         packet_id: VarInt,
-        locale: MCString,
-        view_distance: i8,
-        chat_mode: ChatMode,
-        enable_chat_colors: bool,
-        displayed_skin_parts: u8, //TODO parse bit mask
-        main_hand: Hand,
-        enable_text_filtering: bool,
-        allow_server_listings: bool,
-        particle_status: ParticleStatus,
+        settings: ClientSettings,
     },
     PluginMessage {
         packet_id: VarInt,
-        id: MCString,
-        data: MCString,
+        channel: Identifier,
+        data: Bytes,
     },
     AcknowledgeFinishConfiguration {
         packet_id: VarInt,
     },
+    ResourcePackResponse {
+        packet_id: VarInt,
+        uuid: Uuid,
+        result: ResourcePackStatus,
+    },
+    ConfirmTeleport {
+        packet_id: VarInt,
+        teleport_id: VarInt,
+    },
+    ChatCommand {
+        packet_id: VarInt,
+        command: MCString,
+    },
+    ChatMessage {
+        packet_id: VarInt,
+        message: MCString,
+        timestamp: i64,
+        salt: i64,
+    },
+    PlayerAction {
+        packet_id: VarInt,
+        status: PlayerActionStatus,
+        position: Position,
+        face: BlockFace,
+        sequence: VarInt,
+    },
+    UseItemOn {
+        packet_id: VarInt,
+        hand: InteractionHand,
+        position: Position,
+        face: BlockFace,
+        sequence: VarInt,
+    },
+    /// Any of the four `Set Player Position/Rotation` packets, with the fields they don't carry left out.
+    PlayerMovement {
+        packet_id: VarInt,
+        position: Option<(f64, f64, f64)>,
+        rotation: Option<(f32, f32)>,
+        on_ground: bool,
+    },
+    /// Play packet the server doesn't handle, already skipped.
+    Unknown {
+        packet_id: VarInt,
+    },
+}
+
+impl Request {
+    pub fn packet_id(&self) -> VarInt {
+        match self {
+            Request::Status { packet_id }
+            | Request::Ping { packet_id, .. }
+            | Request::LoginStart { packet_id, .. }
+            | Request::LoginPluginResponse { packet_id, .. }
+            | Request::LoginAcknowledged { packet_id }
+            | Request::ClientConfiguration { packet_id, .. }
+            | Request::PluginMessage { packet_id, .. }
+            | Request::AcknowledgeFinishConfiguration { packet_id }
+            | Request::ResourcePackResponse { packet_id, .. }
+            | Request::ConfirmTeleport { packet_id, .. }
+            | Request::ChatCommand { packet_id, .. }
+            | Request::ChatMessage { packet_id, .. }
+            | Request::PlayerAction { packet_id, .. }
+            | Request::UseItemOn { packet_id, .. }
+            | Request::PlayerMovement { packet_id, .. }
+            | Request::Unknown { packet_id } => *packet_id,
+        }
+    }
 }
 
 pub trait ReadRequest {
@@ -48,56 +117,240 @@ pub trait ReadRequest {
     async fn read_request(&mut self) -> anyhow::Result<Request>;
 }
 
+/// Next thing a connection has to react to: a packet from the client or a command from the server.
+#[derive(Debug)]
+pub enum Incoming {
+    Request(Request),
+    Command(SessionCommand),
+}
+
 impl ReadRequest for ClientConnection<'_> {
     async fn read_request(&mut self) -> anyhow::Result<Request> {
-        let packet_id = self.reader.packet_id().await?;
+        self.track_state();
+        read_request(&mut self.reader, self.state).await
+    }
+}
 
-        match (&self.state, packet_id.into()) {
-            // Status
-            (ClientState::Status, 0x00) => Ok(Request::Status { packet_id }),
-            (ClientState::Status, 0x01) => Ok(Request::Ping {
-                packet_id,
-                timestamp: self.reader.read_i64().await?,
-            }),
-            (ClientState::Status, _) => {
-                bail!("Unknown packet ID: '0x{:02X}' for state: 'Status'", packet_id)
-            }
-            // Login
-            (ClientState::Login, 0x00) => Ok(Request::LoginStart {
+impl ClientConnection<'_> {
+    /// Waits for a request, or for a command sent to the player's session once it is registered.
+    pub async fn next_incoming(&mut self) -> anyhow::Result<Incoming> {
+        self.track_state();
+        let Some(session) = self.session.as_mut() else {
+            return Ok(Incoming::Request(read_request(&mut self.reader, self.state).await?));
+        };
+
+        tokio::select! {
+            biased;
+            Some(command) = session.commands.recv() => Ok(Incoming::Command(command)),
+            request = read_request(&mut self.reader, self.state) => Ok(Incoming::Request(request?)),
+        }
+    }
+}
+
+async fn read_request(reader: &mut ProtocolReader<'_>, state: ClientState) -> anyhow::Result<Request> {
+    let (packet_id, length) = reader.packet_header().await?;
+    METRICS.packet_received(state.label(), packet_id, length);
+
+    let span = debug_span!("decode", packet_id = %format_args!("0x{:02X}", packet_id), length);
+    decode_request(reader, state, packet_id).instrument(span).await
+}
+
+async fn decode_request(
+    reader: &mut ProtocolReader<'_>,
+    state: ClientState,
+    packet_id: VarInt,
+) -> anyhow::Result<Request> {
+    match (&state, packet_id.into()) {
+        // Status
+        (ClientState::Status, 0x00) => Ok(Request::Status { packet_id }),
+        (ClientState::Status, 0x01) => Ok(Request::Ping {
+            packet_id,
+            timestamp: reader.read_i64().await?,
+        }),
+        (ClientState::Status, _) => {
+            bail!("Unknown packet ID: '0x{:02X}' for state: 'Status'", packet_id)
+        }
+        // Login
+        (ClientState::Login, 0x00) => Ok(Request::LoginStart {
+            packet_id,
+            username: reader.read_string().await?,
+            uuid: reader.read_uuid().await?,
+        }),
+        (ClientState::Login, 0x02) => {
+            let message_id = reader.read_varint().await?;
+            // An unsuccessful response has no payload
+            let data = match reader.read_bool().await? {
+                true => Some(reader.read_remaining().await?),
+                false => None,
+            };
+            Ok(Request::LoginPluginResponse {
                 packet_id,
-                username: self.reader.read_string().await?,
-                uuid: self.reader.read_uuid().await?,
-            }),
-            (ClientState::Login, 0x03) => Ok(Request::LoginAcknowledged { packet_id }),
-            (ClientState::Login, _) => {
-                bail!("Unknown packet ID: '0x{:02X}' for state: 'Login'", packet_id)
-            }
-            // Configuration
-            (ClientState::Configuration, 0x00) => Ok(Request::ClientConfiguration {
+                message_id,
+                data,
+            })
+        }
+        (ClientState::Login, 0x03) => Ok(Request::LoginAcknowledged { packet_id }),
+        (ClientState::Login, _) => {
+            bail!("Unknown packet ID: '0x{:02X}' for state: 'Login'", packet_id)
+        }
+        // Configuration
+        (ClientState::Configuration, 0x00) => Ok(Request::ClientConfiguration {
+            packet_id,
+            settings: reader.read().await?,
+        }),
+        (ClientState::Configuration, 0x02) => Ok(Request::PluginMessage {
+            packet_id,
+            channel: reader.read_string().await?,
+            data: reader.read_remaining().await?,
+        }),
+        (ClientState::Configuration, 0x03) => Ok(Request::AcknowledgeFinishConfiguration { packet_id }),
+        (ClientState::Configuration, 0x06) => Ok(Request::ResourcePackResponse {
+            packet_id,
+            uuid: reader.read_uuid().await?,
+            result: reader.read_varint().await?.try_into()?,
+        }),
+        (ClientState::Configuration, _) => {
+            bail!("Unknown packet ID: '0x{:02X}' for state: 'Configuration'", packet_id)
+        }
+        // Play
+        (ClientState::Play, 0x00) => Ok(Request::ConfirmTeleport {
+            packet_id,
+            teleport_id: reader.read_varint().await?,
+        }),
+        (ClientState::Play, 0x06) => Ok(Request::ChatCommand {
+            packet_id,
+            command: reader.read_string().await?,
+        }),
+        (ClientState::Play, 0x08) => {
+            let request = Request::ChatMessage {
                 packet_id,
-                locale: self.reader.read_string().await?,
-                view_distance: self.reader.read_i8().await?,
-                chat_mode: self.reader.read_varint().await?.into(),
-                enable_chat_colors: self.reader.read_bool().await?,
-                displayed_skin_parts: self.reader.read_u8().await?,
-                main_hand: self.reader.read_varint().await?.into(),
-                enable_text_filtering: self.reader.read_bool().await?,
-                allow_server_listings: self.reader.read_bool().await?,
-                particle_status: self.reader.read_varint().await?.into(),
-            }),
-            (ClientState::Configuration, 0x02) => Ok(Request::PluginMessage {
+                message: reader.read_string().await?,
+                timestamp: reader.read_i64().await?,
+                salt: reader.read_i64().await?,
+            };
+            // Signature and acknowledgements are not verified, chat is unsigned
+            reader.read_remaining().await?;
+            Ok(request)
+        }
+        (ClientState::Play, 0x0D) => Ok(Request::ClientConfiguration {
+            packet_id,
+            settings: reader.read().await?,
+        }),
+        (ClientState::Play, 0x15) => Ok(Request::PluginMessage {
+            packet_id,
+            channel: reader.read_string().await?,
+            data: reader.read_remaining().await?,
+        }),
+        (ClientState::Play, id @ 0x1D..=0x20) => {
+            let position = match id {
+                0x1D | 0x1E => Some((reader.read().await?, reader.read().await?, reader.read().await?)),
+                _ => None,
+            };
+            let rotation = match id {
+                0x1E | 0x1F => Some((reader.read().await?, reader.read().await?)),
+                _ => None,
+            };
+            Ok(Request::PlayerMovement {
                 packet_id,
-                id: self.reader.read_string().await?,
-                data: self.reader.read_string().await?,
-            }),
-            (ClientState::Configuration, 0x03) => Ok(Request::AcknowledgeFinishConfiguration { packet_id }),
-            (ClientState::Configuration, _) => {
-                bail!("Unknown packet ID: '0x{:02X}' for state: 'Configuration'", packet_id)
-            }
-            // Play
-            (ClientState::Play, _) => {
-                bail!("Unknown packet ID: '0x{:02X}' for state: 'Play'", packet_id)
+                position,
+                rotation,
+                on_ground: reader.read_u8().await? & 0x01 != 0,
+            })
+        }
+        (ClientState::Play, 0x28) => Ok(Request::PlayerAction {
+            packet_id,
+            status: reader.read_varint().await?.try_into()?,
+            position: reader.read().await?,
+            face: VarInt::new(reader.read_u8().await? as i32).try_into()?,
+            sequence: reader.read_varint().await?,
+        }),
+        (ClientState::Play, 0x30) => Ok(Request::ResourcePackResponse {
+            packet_id,
+            uuid: reader.read_uuid().await?,
+            result: reader.read_varint().await?.try_into()?,
+        }),
+        (ClientState::Play, 0x3F) => {
+            let hand = reader.read_varint().await?.try_into()?;
+            let position = reader.read().await?;
+            let face = reader.read_varint().await?.try_into()?;
+            // Cursor position, inside block and world border hit
+            for _ in 0..3 {
+                reader.read::<f32>().await?;
             }
+            reader.read_bool().await?;
+            reader.read_bool().await?;
+            Ok(Request::UseItemOn {
+                packet_id,
+                hand,
+                position,
+                face,
+                sequence: reader.read_varint().await?,
+            })
+        }
+        (ClientState::Play, _) => {
+            reader.read_remaining().await?;
+            Ok(Request::Unknown { packet_id })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use std::time::Duration;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::time::timeout;
+
+    /// Client and server side of a local connection.
+    async fn connection() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        (client, listener.accept().await.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_empty_packet_followed_by_another() {
+        let (mut client, mut server) = connection().await;
+        let mut reader = ProtocolReader::from_stream(server.split().0).unwrap();
+
+        // Status Request has no body, the client waits for the response before sending the Ping Request
+        client.write_all(&[0x01, 0x00]).await.unwrap();
+        let request = timeout(Duration::from_secs(5), read_request(&mut reader, ClientState::Status))
+            .await
+            .expect("Status Request was not decoded on its own")
+            .unwrap();
+        assert!(matches!(request, Request::Status { .. }));
+
+        client.write_all(&[0x09, 0x01]).await.unwrap();
+        client.write_all(&42i64.to_be_bytes()).await.unwrap();
+        let request = read_request(&mut reader, ClientState::Status).await.unwrap();
+        assert!(matches!(request, Request::Ping { timestamp: 42, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_empty_packet_keeps_the_next() {
+        // Client Tick End without a body, then a Chat Message with an empty signature
+        let mut chat = vec![0x08, 0x02, b'h', b'i'];
+        chat.extend_from_slice(&1i64.to_be_bytes());
+        chat.extend_from_slice(&2i64.to_be_bytes());
+        chat.push(0x00);
+        let mut bytes = vec![0x01, 0x0C, chat.len() as u8];
+        bytes.extend_from_slice(&chat);
+        let (mut client, mut server) = connection().await;
+        client.write_all(&bytes).await.unwrap();
+        let mut reader = ProtocolReader::from_stream(server.split().0).unwrap();
+
+        let request = read_request(&mut reader, ClientState::Play).await.unwrap();
+        assert!(matches!(request, Request::Unknown { .. }));
+        let request = timeout(Duration::from_secs(5), read_request(&mut reader, ClientState::Play))
+            .await
+            .expect("Chat Message was skipped")
+            .unwrap();
+        let Request::ChatMessage { message, salt, .. } = request else {
+            panic!("Expected a chat message, got {:?}", request);
+        };
+        assert_eq!(message.as_str(), "hi");
+        assert_eq!(salt, 2);
+    }
+}
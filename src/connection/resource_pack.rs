@@ -0,0 +1,66 @@
+use super::response::{Response, SendResponse};
+use super::ClientConnection;
+use crate::protocol::types::enums::ResourcePackStatus;
+use anyhow::bail;
+use uuid::Uuid;
+
+/// Resource pack pushed to clients with `Add Resource Pack`.
+#[derive(Debug, Clone)]
+pub struct ResourcePack {
+    pub uuid: Uuid,
+    pub url: String,
+    /// Lowercase hex SHA-1 of the pack archive, or empty to skip the client-side check.
+    pub hash: String,
+    pub forced: bool,
+    pub prompt: Option<String>,
+    /// Disconnect players who decline or fail to load a forced pack.
+    pub kick_on_decline: bool,
+}
+
+impl ResourcePack {
+    pub fn new(url: String, hash: String) -> anyhow::Result<Self> {
+        if !hash.is_empty() && (hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit())) {
+            bail!("Resource pack hash must be a 40 character hex SHA-1, got '{}'", hash);
+        }
+
+        Ok(Self {
+            uuid: Uuid::new_v3(&Uuid::NAMESPACE_URL, url.as_bytes()),
+            url,
+            hash: hash.to_lowercase(),
+            forced: false,
+            prompt: None,
+            kick_on_decline: false,
+        })
+    }
+
+    /// Whether the client must be disconnected after reporting the status.
+    pub fn should_kick(&self, status: ResourcePackStatus) -> bool {
+        self.forced && self.kick_on_decline && status.is_failure()
+    }
+}
+
+impl ClientConnection<'_> {
+    pub async fn push_resource_pack(&mut self, pack: &ResourcePack) -> anyhow::Result<()> {
+        self.send_response(Response::AddResourcePack {
+            uuid: pack.uuid,
+            url: pack.url.clone().into(),
+            hash: pack.hash.clone().into(),
+            forced: pack.forced,
+            prompt: pack.prompt.clone().map(Into::into),
+        })
+        .await
+    }
+
+    /// Removes the pack with the given UUID, or all packs when `None`.
+    pub async fn pop_resource_pack(&mut self, uuid: Option<Uuid>) -> anyhow::Result<()> {
+        match uuid {
+            Some(uuid) => self.resource_packs.remove(&uuid),
+            None => {
+                self.resource_packs.clear();
+                None
+            }
+        };
+
+        self.send_response(Response::RemoveResourcePack { uuid }).await
+    }
+}
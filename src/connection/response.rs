@@ -1,6 +1,12 @@
 use super::ClientConnection;
+use crate::config::ServerConfig;
+use crate::game::PlayerData;
+use crate::protocol::types::enums::ClientState;
 use crate::protocol::types::enums::GameMode;
-use crate::protocol::types::{Identifier, MCString, Position, VarInt};
+use crate::protocol::types::{
+    ClientSettings, GameProfile, Identifier, Location, MCString, Position, RegistryTags, TextComponent, VarInt,
+};
+use bytes::Bytes;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -12,8 +18,12 @@ pub enum Response {
         timestamp: i64,
     },
     LoginSuccess {
-        uuid: Uuid,
-        username: MCString,
+        profile: GameProfile,
+    },
+    LoginPluginRequest {
+        message_id: VarInt,
+        channel: Identifier,
+        data: Bytes,
     },
     LoginDisconnect {
         message: MCString,
@@ -43,9 +53,87 @@ pub enum Response {
         enforces_secure_chat: bool,
     },
     ConfigurationDisconnect {
-        message: MCString,
+        message: TextComponent,
+    },
+    PlayDisconnect {
+        message: TextComponent,
     },
     ConfigurationFinish,
+    FeatureFlags {
+        flags: Vec<Identifier>,
+    },
+    UpdateTags {
+        registries: Vec<RegistryTags>,
+    },
+    PluginMessage {
+        channel: Identifier,
+        data: Bytes,
+    },
+    AddResourcePack {
+        uuid: Uuid,
+        url: MCString,
+        hash: MCString,
+        forced: bool,
+        prompt: Option<TextComponent>,
+    },
+    RemoveResourcePack {
+        uuid: Option<Uuid>,
+    },
+    SystemChat {
+        content: TextComponent,
+        overlay: bool,
+    },
+    /// Moves the player to an absolute location, with no velocity.
+    SynchronizePlayerPosition {
+        teleport_id: VarInt,
+        location: Location,
+    },
+    SetRenderDistance {
+        view_distance: VarInt,
+    },
+    AcknowledgeBlockChange {
+        sequence: VarInt,
+    },
+}
+
+impl Response {
+    /// `Login (play)` for a player joining with their stored data and client settings.
+    pub fn login_play(
+        config: &ServerConfig,
+        entity_id: i32,
+        data: &PlayerData,
+        settings: Option<&ClientSettings>,
+    ) -> Self {
+        let (gameplay, world) = (&config.gameplay, &config.world);
+        Response::LoginPlay {
+            entity_id,
+            is_hardcore: gameplay.hardcore,
+            dimension_names: vec![world.dimension.clone().into()],
+            max_players: (gameplay.max_players as i32).into(),
+            simulation_distance: (gameplay.simulation_distance as i32).into(),
+            reduced_debug_info: gameplay.reduced_debug_info,
+            view_distance: settings
+                .map_or(gameplay.view_distance as i32, |settings| {
+                    settings.effective_view_distance(gameplay.view_distance as i32)
+                })
+                .into(),
+            enable_respawn_screen: gameplay.enable_respawn_screen,
+            do_limited_crafting: false,
+            dimension_type: 0.into(),
+            dimension_name: world.dimension.clone().into(),
+            hashed_seed: 0,
+            game_mode: data.game_mode,
+            previous_game_mode: data.previous_game_mode.unwrap_or(GameMode::Undefined),
+            is_debug: world.debug,
+            is_flat: world.flat,
+            has_death_location: data.death_location.is_some(),
+            death_dimension_name: data.death_location.as_ref().map(|death| death.dimension.clone().into()),
+            death_location: data.death_location.as_ref().map(|death| death.position()),
+            portal_cooldown: 0.into(),
+            sea_level: world.sea_level.into(),
+            enforces_secure_chat: gameplay.enforce_secure_chat,
+        }
+    }
 }
 
 pub trait SendResponse {
@@ -55,20 +143,30 @@ pub trait SendResponse {
 
 impl SendResponse for ClientConnection<'_> {
     async fn send_response(&mut self, response: Response) -> anyhow::Result<()> {
+        self.track_state();
+
         match response {
             Response::Status { cluster_info } => {
                 self.writer.write(cluster_info)?;
-                self.writer.send_packet(0x00.into()).await
+                self.writer.send_packet(self.state, 0x00.into()).await
             }
             Response::LoginPong { timestamp } => {
                 self.writer.write(timestamp)?;
-                self.writer.send_packet(0x01.into()).await
+                self.writer.send_packet(self.state, 0x01.into()).await
             }
-            Response::LoginSuccess { uuid, username } => {
-                self.writer.write(uuid)?;
-                self.writer.write(username)?;
-                self.writer.write(VarInt::new(0))?;
-                self.writer.send_packet(0x02.into()).await
+            Response::LoginSuccess { profile } => {
+                self.writer.write(profile)?;
+                self.writer.send_packet(self.state, 0x02.into()).await
+            }
+            Response::LoginPluginRequest {
+                message_id,
+                channel,
+                data,
+            } => {
+                self.writer.write(message_id)?;
+                self.writer.write(channel)?;
+                self.writer.write(data)?;
+                self.writer.send_packet(self.state, 0x04.into()).await
             }
             Response::LoginPlay {
                 entity_id,
@@ -121,17 +219,95 @@ impl SendResponse for ClientConnection<'_> {
                 self.writer.write(sea_level)?;
                 self.writer.write(enforces_secure_chat)?;
 
-                self.writer.send_packet(0x2B.into()).await
+                self.writer.send_packet(self.state, 0x2B.into()).await
             }
             Response::LoginDisconnect { message } => {
                 self.writer.write(message)?;
-                self.writer.send_packet(0x00.into()).await
+                self.writer.send_packet(self.state, 0x00.into()).await
             }
             Response::ConfigurationDisconnect { message } => {
                 self.writer.write(message)?;
-                self.writer.send_packet(0x02.into()).await
+                self.writer.send_packet(self.state, 0x02.into()).await
+            }
+            Response::PlayDisconnect { message } => {
+                self.writer.write(message)?;
+                self.writer.send_packet(self.state, 0x1C.into()).await
+            }
+            Response::ConfigurationFinish => self.writer.send_packet(self.state, 0x03.into()).await,
+            Response::FeatureFlags { flags } => {
+                self.writer.write(flags)?;
+                self.writer.send_packet(self.state, 0x0C.into()).await
+            }
+            Response::UpdateTags { registries } => {
+                self.writer.write(registries)?;
+                self.writer.send_packet(self.state, 0x0D.into()).await
+            }
+            Response::PluginMessage { channel, data } => {
+                self.writer.write(channel)?;
+                self.writer.write(data)?;
+                match self.state {
+                    ClientState::Play => self.writer.send_packet(self.state, 0x18.into()).await,
+                    _ => self.writer.send_packet(self.state, 0x01.into()).await,
+                }
+            }
+            Response::AddResourcePack {
+                uuid,
+                url,
+                hash,
+                forced,
+                prompt,
+            } => {
+                self.writer.write(uuid)?;
+                self.writer.write(url)?;
+                self.writer.write(hash)?;
+                self.writer.write(forced)?;
+                self.writer.write(prompt.is_some())?;
+                if let Some(prompt) = prompt {
+                    self.writer.write(prompt)?;
+                }
+                match self.state {
+                    ClientState::Play => self.writer.send_packet(self.state, 0x4A.into()).await,
+                    _ => self.writer.send_packet(self.state, 0x09.into()).await,
+                }
+            }
+            Response::RemoveResourcePack { uuid } => {
+                self.writer.write(uuid.is_some())?;
+                if let Some(uuid) = uuid {
+                    self.writer.write(uuid)?;
+                }
+                match self.state {
+                    ClientState::Play => self.writer.send_packet(self.state, 0x49.into()).await,
+                    _ => self.writer.send_packet(self.state, 0x08.into()).await,
+                }
+            }
+            Response::SystemChat { content, overlay } => {
+                self.writer.write(content)?;
+                self.writer.write(overlay)?;
+                self.writer.send_packet(self.state, 0x72.into()).await
+            }
+            Response::SynchronizePlayerPosition { teleport_id, location } => {
+                self.writer.write(teleport_id)?;
+                self.writer.write(location.x)?;
+                self.writer.write(location.y)?;
+                self.writer.write(location.z)?;
+                // Velocity
+                for _ in 0..3 {
+                    self.writer.write(0f64)?;
+                }
+                self.writer.write(location.yaw)?;
+                self.writer.write(location.pitch)?;
+                // No relative fields
+                self.writer.write(0i32)?;
+                self.writer.send_packet(self.state, 0x41.into()).await
+            }
+            Response::SetRenderDistance { view_distance } => {
+                self.writer.write(view_distance)?;
+                self.writer.send_packet(self.state, 0x58.into()).await
+            }
+            Response::AcknowledgeBlockChange { sequence } => {
+                self.writer.write(sequence)?;
+                self.writer.send_packet(self.state, 0x04.into()).await
             }
-            Response::ConfigurationFinish => self.writer.send_packet(0x03.into()).await,
         }
     }
 }
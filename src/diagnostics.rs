@@ -0,0 +1,5 @@
+mod profiler;
+mod watchdog;
+
+pub use profiler::{ProfileReport, Profiler, Timing};
+pub use watchdog::{spawn as spawn_watchdog, WatchGuard, Watchdog};
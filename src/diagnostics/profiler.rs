@@ -0,0 +1,243 @@
+use crate::game::TICK_DURATION;
+use anyhow::bail;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Calls and time spent by a system or plugin while profiling.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timing {
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl Timing {
+    fn record(&mut self, duration: Duration) {
+        self.calls += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn average(&self) -> Duration {
+        self.total.checked_div(self.calls as u32).unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct Session {
+    started: Instant,
+    started_at: SystemTime,
+    duration: Duration,
+    requester: Option<Uuid>,
+    ticks: Timing,
+    systems: Vec<(&'static str, Timing)>,
+    plugins: HashMap<String, Timing>,
+}
+
+/// Records per-system and per-plugin timings of the game loop for the `profile` command.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    active: AtomicBool,
+    session: Mutex<Option<Session>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts profiling, the report is returned by `poll` once the duration elapsed.
+    pub fn start(&self, duration: Duration, requester: Option<Uuid>) -> anyhow::Result<()> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_some() {
+            bail!("A profile is already being recorded");
+        }
+        *session = Some(Session {
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            duration,
+            requester,
+            ticks: Timing::default(),
+            systems: Vec::new(),
+            plugins: HashMap::new(),
+        });
+        self.active.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Cheap check before timing anything.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub fn record_tick(&self, duration: Duration) {
+        self.record(|session| session.ticks.record(duration));
+    }
+
+    pub fn record_system(&self, name: &'static str, duration: Duration) {
+        self.record(
+            |session| match session.systems.iter_mut().find(|(system, _)| *system == name) {
+                Some((_, timing)) => timing.record(duration),
+                None => {
+                    let mut timing = Timing::default();
+                    timing.record(duration);
+                    session.systems.push((name, timing));
+                }
+            },
+        );
+    }
+
+    pub fn record_plugin(&self, name: &str, duration: Duration) {
+        self.record(|session| match session.plugins.get_mut(name) {
+            Some(timing) => timing.record(duration),
+            None => session.plugins.entry(name.to_string()).or_default().record(duration),
+        });
+    }
+
+    fn record(&self, f: impl FnOnce(&mut Session)) {
+        if let Some(session) = self.session.lock().unwrap().as_mut() {
+            f(session);
+        }
+    }
+
+    /// Ends the profile once its duration elapsed. Called by the game loop after every tick.
+    pub fn poll(&self) -> Option<ProfileReport> {
+        let mut session = self.session.lock().unwrap();
+        if session.as_ref()?.started.elapsed() < session.as_ref()?.duration {
+            return None;
+        }
+        let session = session.take()?;
+        self.active.store(false, Ordering::Release);
+
+        let mut plugins: Vec<_> = session.plugins.into_iter().collect();
+        plugins.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.total));
+        Some(ProfileReport {
+            started_at: session.started_at,
+            duration: session.started.elapsed(),
+            requester: session.requester,
+            ticks: session.ticks,
+            systems: session.systems,
+            plugins,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileReport {
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    /// Player who started the profile, to be told where the report was written.
+    pub requester: Option<Uuid>,
+    pub ticks: Timing,
+    pub systems: Vec<(&'static str, Timing)>,
+    /// Sorted by total time, slowest first.
+    pub plugins: Vec<(String, Timing)>,
+}
+
+impl ProfileReport {
+    pub fn render(&self) -> String {
+        let seconds = self.duration.as_secs_f64();
+        let over_budget = self.ticks.max > TICK_DURATION;
+        let mut report = String::new();
+        let _ = writeln!(
+            report,
+            "Profile started at {} (unix), recorded for {:.1}s",
+            unix_secs(self.started_at),
+            seconds
+        );
+        let _ = writeln!(
+            report,
+            "Ticks: {} ({:.1} TPS), {:.2} ms average, {:.2} ms peak{}",
+            self.ticks.calls,
+            self.ticks.calls as f64 / seconds.max(f64::EPSILON),
+            millis(self.ticks.average()),
+            millis(self.ticks.max),
+            if over_budget { ", over budget" } else { "" }
+        );
+
+        let tick_total = self.ticks.total.as_secs_f64().max(f64::EPSILON);
+        section(&mut report, "System", &self.systems, tick_total);
+        section(&mut report, "Plugin", &self.plugins, tick_total);
+        report
+    }
+
+    /// Writes the report to `profile-<unix time>.txt` in the directory and returns its path.
+    pub fn write(&self, directory: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(format!("profile-{}.txt", unix_secs(self.started_at)));
+        std::fs::write(&path, self.render())?;
+        Ok(path)
+    }
+}
+
+fn section<N: AsRef<str>>(report: &mut String, title: &str, rows: &[(N, Timing)], tick_total: f64) {
+    let _ = writeln!(
+        report,
+        "\n{:<24} {:>8} {:>10} {:>8} {:>8} {:>7}",
+        title, "Calls", "Total ms", "Avg ms", "Max ms", "% tick"
+    );
+    if rows.is_empty() {
+        let _ = writeln!(report, "(none)");
+    }
+    for (name, timing) in rows {
+        let _ = writeln!(
+            report,
+            "{:<24} {:>8} {:>10.2} {:>8.3} {:>8.3} {:>6.1}%",
+            name.as_ref(),
+            timing.calls,
+            millis(timing.total),
+            millis(timing.average()),
+            millis(timing.max),
+            timing.total.as_secs_f64() / tick_total * 100.0
+        );
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_report() {
+        let profiler = Profiler::new();
+        assert!(!profiler.is_active());
+        profiler.start(Duration::ZERO, None).unwrap();
+        assert!(profiler.start(Duration::ZERO, None).is_err());
+
+        profiler.record_tick(Duration::from_millis(4));
+        profiler.record_system("movement", Duration::from_millis(1));
+        profiler.record_system("movement", Duration::from_millis(3));
+        profiler.record_plugin("wasm/hello", Duration::from_millis(2));
+
+        let report = profiler.poll().unwrap();
+        assert!(!profiler.is_active());
+        assert_eq!(
+            report.systems,
+            vec![(
+                "movement",
+                Timing {
+                    calls: 2,
+                    total: Duration::from_millis(4),
+                    max: Duration::from_millis(3),
+                }
+            )]
+        );
+
+        let rendered = report.render();
+        assert!(rendered.contains("movement                        2       4.00    2.000    3.000  100.0%"));
+        assert!(rendered.contains("wasm/hello"));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::server::Server;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const MIN_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct Running {
+    what: String,
+    /// What the task is busy with right now, like the system of a tick.
+    detail: Option<String>,
+    started: Instant,
+    reported: bool,
+}
+
+impl Running {
+    fn describe(&self, now: Instant) -> String {
+        let elapsed = now.duration_since(self.started).as_millis();
+        match &self.detail {
+            Some(detail) => format!("{} ({}) for {}ms", self.what, detail, elapsed),
+            None => format!("{} for {}ms", self.what, elapsed),
+        }
+    }
+}
+
+/// Keeps track of running ticks, handlers and plugins, to report the ones exceeding the threshold.
+#[derive(Debug)]
+pub struct Watchdog {
+    threshold: Duration,
+    running: Mutex<BTreeMap<u64, Running>>,
+    next_id: AtomicU64,
+}
+
+impl Watchdog {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            running: Mutex::default(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Tracks a task until the guard is dropped.
+    pub fn enter(&self, what: impl Into<String>) -> WatchGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let running = Running {
+            what: what.into(),
+            detail: None,
+            started: Instant::now(),
+            reported: false,
+        };
+        self.running.lock().unwrap().insert(id, running);
+        WatchGuard { watchdog: self, id }
+    }
+
+    /// Describes every running task once one of them newly exceeded the threshold, oldest first.
+    pub fn check(&self) -> Option<String> {
+        let now = Instant::now();
+        let mut running = self.running.lock().unwrap();
+
+        let mut overdue = false;
+        for task in running.values_mut() {
+            if !task.reported && now.duration_since(task.started) > self.threshold {
+                task.reported = true;
+                overdue = true;
+            }
+        }
+        if !overdue {
+            return None;
+        }
+
+        let mut dump = String::from("Running tasks:");
+        for task in running.values() {
+            let _ = write!(dump, "\n  {}", task.describe(now));
+        }
+        Some(dump)
+    }
+
+    fn leave(&self, id: u64) {
+        let Some(task) = self.running.lock().unwrap().remove(&id) else {
+            return;
+        };
+        let elapsed = task.started.elapsed();
+        if task.reported {
+            info!("Watchdog: {} finished after {}ms", task.what, elapsed.as_millis());
+        } else if elapsed > self.threshold {
+            // Finished between two checks
+            warn!("Watchdog: {}", task.describe(Instant::now()));
+        }
+    }
+}
+
+pub struct WatchGuard<'a> {
+    watchdog: &'a Watchdog,
+    id: u64,
+}
+
+impl WatchGuard<'_> {
+    pub fn detail(&self, detail: impl Into<String>) {
+        if let Some(task) = self.watchdog.running.lock().unwrap().get_mut(&self.id) {
+            task.detail = Some(detail.into());
+        }
+    }
+}
+
+impl Drop for WatchGuard<'_> {
+    fn drop(&mut self) {
+        self.watchdog.leave(self.id);
+    }
+}
+
+/// Checks the running tasks on a dedicated thread until the server shuts down.
+pub fn spawn(server: Arc<Server>) -> std::io::Result<JoinHandle<()>> {
+    let interval = (server.watchdog.threshold / 4).max(MIN_CHECK_INTERVAL);
+    std::thread::Builder::new().name("watchdog".into()).spawn(move || {
+        while !server.is_shutting_down() {
+            std::thread::sleep(interval);
+            if let Some(dump) = server.watchdog.check() {
+                warn!(
+                    "Watchdog: a task exceeded {}ms. {}",
+                    server.watchdog.threshold.as_millis(),
+                    dump
+                );
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_overdue_once() {
+        let watchdog = Watchdog::new(Duration::ZERO);
+        let tick = watchdog.enter("tick 42");
+        tick.detail("system movement");
+        std::thread::sleep(Duration::from_millis(1));
+
+        let dump = watchdog.check().unwrap();
+        assert!(dump.contains("tick 42 (system movement) for"));
+        assert!(watchdog.check().is_none());
+
+        drop(tick);
+        assert!(watchdog.running.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+mod player_data;
+mod scheduler;
+mod systems;
+mod timings;
+mod world;
+
+use crate::diagnostics::{ProfileReport, WatchGuard};
+use crate::metrics::METRICS;
+use crate::protocol::types::enums::{BlockFace, InteractionHand, PlayerActionStatus};
+use crate::protocol::types::{GameProfile, Position, TextComponent, VarInt};
+use crate::server::{Server, SessionCommand};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+pub use player_data::{GlobalPosition, ItemStack, PlayerData, PlayerSaves, SpawnPoint};
+pub use scheduler::{Scheduler, TaskHandle};
+pub use timings::TickTimings;
+pub use world::{PlayerEntity, World};
+
+pub const TPS: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TPS as u64);
+/// Ticks are run back to back to catch up on a lag spike up to this long, longer ones are skipped.
+const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+const OVERLOAD_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Input of a player for the game loop, queued by its connection.
+#[derive(Debug)]
+pub enum PlayerAction {
+    Join {
+        profile: GameProfile,
+        data: Box<PlayerData>,
+    },
+    Move {
+        position: Option<(f64, f64, f64)>,
+        rotation: Option<(f32, f32)>,
+        on_ground: bool,
+    },
+    Chat {
+        message: String,
+    },
+    Dig {
+        status: PlayerActionStatus,
+        position: Position,
+        sequence: VarInt,
+    },
+    UseItemOn {
+        hand: InteractionHand,
+        position: Position,
+        face: BlockFace,
+        sequence: VarInt,
+    },
+    Leave,
+}
+
+#[derive(Debug)]
+pub struct QueuedAction {
+    pub entity_id: i32,
+    pub action: PlayerAction,
+}
+
+/// Connection side of the game loop: queues player actions and reports tick timings.
+pub struct Game {
+    actions: mpsc::UnboundedSender<QueuedAction>,
+    queue: Mutex<Option<mpsc::UnboundedReceiver<QueuedAction>>>,
+    timings: Mutex<TickTimings>,
+    saves: PlayerSaves,
+    stopping: AtomicBool,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        let (actions, queue) = mpsc::unbounded_channel();
+        Self {
+            actions,
+            queue: Mutex::new(Some(queue)),
+            timings: Mutex::default(),
+            saves: PlayerSaves::new(),
+            stopping: AtomicBool::new(false),
+        }
+    }
+
+    /// Handles the action on the next tick.
+    pub fn queue(&self, entity_id: i32, action: PlayerAction) {
+        let _ = self.actions.send(QueuedAction { entity_id, action });
+    }
+
+    /// Queues the `Join` of the player, and their `Leave` once the returned guard is dropped.
+    pub fn join(&self, entity_id: i32, profile: GameProfile, data: PlayerData) -> JoinedGame {
+        self.saves.joined(profile.uuid);
+        self.queue(
+            entity_id,
+            PlayerAction::Join {
+                profile,
+                data: Box::new(data),
+            },
+        );
+        JoinedGame {
+            entity_id,
+            actions: self.actions.clone(),
+        }
+    }
+
+    /// Ends the game loop after one last tick handling the remaining actions.
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::Release);
+    }
+
+    pub fn timings(&self) -> TickTimings {
+        self.timings.lock().unwrap().clone()
+    }
+
+    /// Player data saves in progress, to wait for before loading a player or exiting.
+    pub fn saves(&self) -> &PlayerSaves {
+        &self.saves
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Player in the game loop, owned by its connection. Dropping it makes the player leave,
+/// even when the connection task panics or is cancelled.
+#[derive(Debug)]
+pub struct JoinedGame {
+    entity_id: i32,
+    actions: mpsc::UnboundedSender<QueuedAction>,
+}
+
+impl Drop for JoinedGame {
+    fn drop(&mut self) {
+        let _ = self.actions.send(QueuedAction {
+            entity_id: self.entity_id,
+            action: PlayerAction::Leave,
+        });
+    }
+}
+
+/// Packets produced during a tick, sent to the connections once it ends.
+#[derive(Debug, Default)]
+pub struct Outbound {
+    commands: Vec<(Option<Uuid>, SessionCommand)>,
+}
+
+impl Outbound {
+    pub fn send(&mut self, player: Uuid, command: SessionCommand) {
+        self.commands.push((Some(player), command));
+    }
+
+    /// Sends a copy of the command to every player when the tick ends.
+    pub fn broadcast(&mut self, command: SessionCommand) {
+        self.commands.push((None, command));
+    }
+
+    fn flush(&mut self, server: &Server) {
+        for (player, command) in self.commands.drain(..) {
+            match (player, command) {
+                (Some(uuid), command) => {
+                    server.sessions.send(&uuid, command);
+                }
+                (None, SessionCommand::Message(content)) => {
+                    server.sessions.broadcast(|| SessionCommand::Message(content.clone()));
+                }
+                (None, SessionCommand::ActionBar(content)) => {
+                    server.sessions.broadcast(|| SessionCommand::ActionBar(content.clone()));
+                }
+                (None, command) => warn!(?command, "Command can't be broadcast"),
+            }
+        }
+    }
+}
+
+/// State of a single tick, passed to every system.
+pub struct Tick<'a> {
+    pub server: &'a Server,
+    pub outbound: Outbound,
+    /// Tells the watchdog what the tick is busy with.
+    pub watch: WatchGuard<'a>,
+}
+
+/// Starts the game loop on its own thread, running until `Game::stop` is called.
+pub fn spawn(server: Arc<Server>) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("game".into())
+        .spawn(move || run(&server))
+}
+
+fn run(server: &Server) {
+    let Some(mut queue) = server.game.queue.lock().unwrap().take() else {
+        return;
+    };
+    let mut world = World::new();
+    let mut next_tick = Instant::now();
+    let mut last_warning: Option<Instant> = None;
+    info!(tps = TPS, "Game loop started");
+
+    loop {
+        let stopping = server.game.stopping.load(Ordering::Acquire);
+
+        let start = Instant::now();
+        let actions: Vec<QueuedAction> = std::iter::from_fn(|| queue.try_recv().ok()).collect();
+        let mut tick = Tick {
+            server,
+            outbound: Outbound::default(),
+            watch: server.watchdog.enter(format!("tick {}", world.tick)),
+        };
+        systems::run(&mut world, &actions, &mut tick);
+        tick.watch.detail("sending packets");
+        tick.outbound.flush(server);
+        drop(tick);
+        world.tick += 1;
+
+        let duration = start.elapsed();
+        METRICS.tick_duration.observe(duration.as_secs_f64());
+        server.game.timings.lock().unwrap().record(start, duration);
+        if server.profiler.is_active() {
+            server.profiler.record_tick(duration);
+            if let Some(report) = server.profiler.poll() {
+                write_profile(server, &report);
+            }
+        }
+
+        if stopping {
+            // Players still connected never send their Leave once the loop has stopped
+            for player in world.players.values() {
+                player_data::save(server, player);
+            }
+            break;
+        }
+
+        next_tick += TICK_DURATION;
+        let now = Instant::now();
+        if now < next_tick {
+            std::thread::sleep(next_tick - now);
+        } else if now - next_tick > MAX_CATCH_UP {
+            let behind = now - next_tick;
+            let skipped = (behind.as_millis() / TICK_DURATION.as_millis()) as u64;
+            if last_warning.is_none_or(|warning| now - warning > OVERLOAD_WARNING_INTERVAL) {
+                warn!(
+                    "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+                    behind.as_millis(),
+                    skipped
+                );
+                last_warning = Some(now);
+            }
+            server.game.timings.lock().unwrap().skipped += skipped;
+            next_tick = now;
+        }
+    }
+
+    info!(ticks = world.tick, "Game loop stopped");
+}
+
+fn write_profile(server: &Server, report: &ProfileReport) {
+    let message = match report.write(&server.config.diagnostics.profile_directory) {
+        Ok(path) => {
+            info!(path = %path.display(), "Profile written");
+            TextComponent::new(format!("Profile written to {}", path.display()))
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to write profile");
+            TextComponent::new("Failed to write the profile, see the server log").color("red")
+        }
+    };
+    if let Some(requester) = report.requester {
+        server.sessions.send(&requester, SessionCommand::Message(message));
+    }
+}
@@ -0,0 +1,412 @@
+use super::PlayerEntity;
+use crate::config::ServerConfig;
+use crate::protocol::types::enums::GameMode;
+use crate::protocol::types::{Location, Position};
+use crate::server::Server;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Longest a joining player waits for the data saved by their previous session.
+const PREVIOUS_SAVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Block in a dimension, like the last death location of a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalPosition {
+    pub dimension: String,
+    pub x: i32,
+    pub y: i16,
+    pub z: i32,
+}
+
+impl GlobalPosition {
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y, self.z)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    pub position: GlobalPosition,
+    pub angle: f32,
+    /// Whether the spawn point was set without a bed or respawn anchor.
+    pub forced: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub slot: u8,
+    pub item: String,
+    pub count: u8,
+}
+
+/// State of a player saved across sessions, stored as JSON keyed by UUID.
+///
+/// Missing fields take their default, so data saved by older versions still loads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerData {
+    pub location: Location,
+    pub dimension: String,
+    pub game_mode: GameMode,
+    pub previous_game_mode: Option<GameMode>,
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    pub xp_level: i32,
+    /// Progress towards the next level, from 0 to 1.
+    pub xp_progress: f32,
+    pub xp_total: i32,
+    pub inventory: Vec<ItemStack>,
+    pub ender_chest: Vec<ItemStack>,
+    pub death_location: Option<GlobalPosition>,
+    pub spawn_point: Option<SpawnPoint>,
+}
+
+impl Default for PlayerData {
+    fn default() -> Self {
+        Self {
+            location: Location::default(),
+            dimension: "minecraft:overworld".into(),
+            game_mode: GameMode::Survival,
+            previous_game_mode: None,
+            health: 20.0,
+            food: 20,
+            saturation: 5.0,
+            xp_level: 0,
+            xp_progress: 0.0,
+            xp_total: 0,
+            inventory: Vec::new(),
+            ender_chest: Vec::new(),
+            death_location: None,
+            spawn_point: None,
+        }
+    }
+}
+
+impl PlayerData {
+    /// Data of a player joining for the first time, at the world spawn.
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            location: world_spawn(config),
+            dimension: config.world.dimension.clone(),
+            game_mode: config.gameplay.game_mode,
+            ..Default::default()
+        }
+    }
+
+    /// Stored data of the player, or the data of a new player.
+    ///
+    /// Players saved in another dimension than the one of the world are moved to the world spawn.
+    pub async fn load(server: &Server, uuid: Uuid) -> anyhow::Result<Self> {
+        // Loading before the previous session is saved would restore, then save over, stale data
+        if tokio::time::timeout(PREVIOUS_SAVE_TIMEOUT, server.game.saves().wait(uuid))
+            .await
+            .is_err()
+        {
+            bail!("Timed out waiting for the previous session of the player to be saved");
+        }
+
+        let Some(data) = server.storage.player_data(uuid).await? else {
+            return Ok(Self::new(&server.config));
+        };
+        let mut data: Self = serde_json::from_slice(&data).context("Invalid player data")?;
+
+        if data.dimension != server.config.world.dimension {
+            data.dimension = server.config.world.dimension.clone();
+            data.location = world_spawn(&server.config);
+        }
+        Ok(data)
+    }
+}
+
+fn world_spawn(config: &ServerConfig) -> Location {
+    Location::new(0.5, config.world.sea_level as f64 + 1.0, 0.5)
+}
+
+/// Saves the data of the player in the background, logging failures.
+pub fn save(server: &Server, player: &PlayerEntity) {
+    queue_save(server, player, false);
+}
+
+/// Saves the data of a player who left the game loop, letting their next session load it.
+pub fn save_on_leave(server: &Server, player: &PlayerEntity) {
+    queue_save(server, player, true);
+}
+
+fn queue_save(server: &Server, player: &PlayerEntity, left: bool) {
+    let data = serde_json::to_vec(&player.data)
+        .inspect_err(|e| warn!(player = %player.profile.name, error = %e, "Failed to serialize player data"))
+        .ok();
+
+    let saves = server.game.saves().clone();
+    let uuid = player.profile.uuid;
+    if !saves.queue(uuid, data, left) {
+        return;
+    }
+
+    let storage = server.storage.clone();
+    let name = player.profile.name.clone();
+    server.scheduler.run_async(
+        async move {
+            while let Some(data) = saves.next(uuid) {
+                if let Err(e) = storage.save_player_data(uuid, data).await {
+                    warn!(player = %name, error = %e, "Failed to save player data");
+                }
+            }
+        },
+        |_, _, _| {},
+    );
+}
+
+/// Saves of player data not written yet. Saves of a player are written one at a time, in order,
+/// and a player joining again waits until the data of their previous session is written.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerSaves {
+    inner: Arc<SavesInner>,
+}
+
+#[derive(Debug, Default)]
+struct SavesInner {
+    players: Mutex<HashMap<Uuid, PendingSaves>>,
+    /// Notified when a player has no save left to write.
+    written: Notify,
+}
+
+#[derive(Debug, Default)]
+struct PendingSaves {
+    /// Sessions of the player in the game loop, saved once they leave.
+    in_game: usize,
+    /// Newest data not written yet, replacing older data that was not written either.
+    queued: Option<Vec<u8>>,
+    writing: bool,
+}
+
+impl PlayerSaves {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a session of the player in the game loop until it is saved with `save_on_leave`.
+    pub fn joined(&self, uuid: Uuid) {
+        self.inner.players.lock().unwrap().entry(uuid).or_default().in_game += 1;
+    }
+
+    /// Queues the data to be written, returns whether a writer has to be started for the player.
+    fn queue(&self, uuid: Uuid, data: Option<Vec<u8>>, left: bool) -> bool {
+        let mut players = self.inner.players.lock().unwrap();
+        let pending = players.entry(uuid).or_default();
+        if left {
+            pending.in_game = pending.in_game.saturating_sub(1);
+        }
+        if data.is_some() {
+            pending.queued = data;
+        }
+
+        if pending.writing {
+            false
+        } else if pending.queued.is_some() {
+            pending.writing = true;
+            true
+        } else {
+            if pending.in_game == 0 {
+                players.remove(&uuid);
+            }
+            self.inner.written.notify_waiters();
+            false
+        }
+    }
+
+    /// Next data for the writer of the player, `None` once everything is written.
+    fn next(&self, uuid: Uuid) -> Option<Vec<u8>> {
+        let mut players = self.inner.players.lock().unwrap();
+        let pending = players.get_mut(&uuid)?;
+        if let Some(data) = pending.queued.take() {
+            return Some(data);
+        }
+
+        pending.writing = false;
+        if pending.in_game == 0 {
+            players.remove(&uuid);
+        }
+        self.inner.written.notify_waiters();
+        None
+    }
+
+    /// Waits until every session of the player has left the game loop and their data is written.
+    pub async fn wait(&self, uuid: Uuid) {
+        self.wait_until(|players| !players.contains_key(&uuid)).await
+    }
+
+    /// Waits until every queued save is written.
+    pub async fn flush(&self) {
+        self.wait_until(|players| !players.values().any(|pending| pending.writing))
+            .await
+    }
+
+    async fn wait_until(&self, done: impl Fn(&HashMap<Uuid, PendingSaves>) -> bool) {
+        loop {
+            // Created before checking, so a notification in between isn't missed
+            let written = self.inner.written.notified();
+            if done(&self.inner.players.lock().unwrap()) {
+                return;
+            }
+            written.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::response::{Response, SendResponse};
+    use crate::connection::ClientConnection;
+    use crate::protocol::types::{GameProfile, VarInt};
+    use crate::storage::MemoryStorage;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn server() -> Server {
+        Server::new(ServerConfig::default(), Arc::new(MemoryStorage::new())).unwrap()
+    }
+
+    fn player(data: PlayerData) -> PlayerEntity {
+        PlayerEntity {
+            entity_id: 1,
+            profile: GameProfile::new(Uuid::new_v4(), "Steve"),
+            on_ground: true,
+            data,
+        }
+    }
+
+    fn veteran(config: &ServerConfig) -> PlayerData {
+        PlayerData {
+            location: Location::new(10.5, 70.0, -3.5),
+            game_mode: GameMode::Creative,
+            previous_game_mode: Some(GameMode::Survival),
+            xp_level: 12,
+            inventory: vec![ItemStack {
+                slot: 36,
+                item: "minecraft:diamond_pickaxe".into(),
+                count: 1,
+            }],
+            death_location: Some(GlobalPosition {
+                dimension: "minecraft:overworld".into(),
+                x: 120,
+                y: 12,
+                z: -40,
+            }),
+            ..PlayerData::new(config)
+        }
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let data: PlayerData = serde_json::from_str(r#"{"game_mode":"creative","xp_level":30}"#).unwrap();
+
+        assert_eq!(data.game_mode, GameMode::Creative);
+        assert_eq!(data.xp_level, 30);
+        assert_eq!(data.health, 20.0);
+        assert_eq!(data.previous_game_mode, None);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let server = server();
+        let player = player(veteran(&server.config));
+        let uuid = player.profile.uuid;
+
+        assert_eq!(PlayerData::load(&server, uuid).await.unwrap(), PlayerData::new(&server.config));
+
+        server.game.saves().joined(uuid);
+        save_on_leave(&server, &player);
+        assert_eq!(PlayerData::load(&server, uuid).await.unwrap(), player.data);
+    }
+
+    #[tokio::test]
+    async fn test_load_waits_for_previous_session() {
+        let server = server();
+        let mut player = player(PlayerData::new(&server.config));
+        let uuid = player.profile.uuid;
+
+        server.game.saves().joined(uuid);
+        save(&server, &player);
+        let rejoin = tokio::time::timeout(Duration::from_millis(50), PlayerData::load(&server, uuid));
+        assert!(rejoin.await.is_err());
+
+        player.data.xp_level = 5;
+        save_on_leave(&server, &player);
+        assert_eq!(PlayerData::load(&server, uuid).await.unwrap().xp_level, 5);
+        server.game.saves().flush().await;
+    }
+
+    #[tokio::test]
+    async fn test_other_dimension_moves_to_world_spawn() {
+        let server = server();
+        let mut data = veteran(&server.config);
+        data.dimension = "minecraft:the_nether".into();
+        let uuid = Uuid::new_v4();
+        let stored = serde_json::to_vec(&data).unwrap();
+        server.storage.save_player_data(uuid, stored).await.unwrap();
+
+        let loaded = PlayerData::load(&server, uuid).await.unwrap();
+        assert_eq!(loaded.dimension, server.config.world.dimension);
+        assert_eq!(loaded.location, world_spawn(&server.config));
+        assert_eq!(loaded.game_mode, GameMode::Creative);
+        assert_eq!(loaded.xp_level, 12);
+    }
+
+    #[tokio::test]
+    async fn test_login_play_from_stored_data() {
+        let config = ServerConfig::default();
+        let mut data = veteran(&config);
+
+        let Response::LoginPlay {
+            view_distance,
+            game_mode,
+            previous_game_mode,
+            has_death_location,
+            death_dimension_name,
+            death_location,
+            ..
+        } = Response::login_play(&config, 1, &data, None)
+        else {
+            panic!("Expected Login (play)");
+        };
+        assert_eq!(view_distance, VarInt::new(8));
+        assert_eq!(game_mode, GameMode::Creative);
+        assert_eq!(previous_game_mode, GameMode::Survival);
+        assert!(has_death_location);
+        assert_eq!(death_dimension_name, Some("minecraft:overworld".into()));
+        assert_eq!(death_location, Some(Position::new(120, 12, -40)));
+
+        // A first join has no previous game mode, sent as -1
+        data.previous_game_mode = None;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (mut stream, addr) = listener.accept().await.unwrap();
+        let mut conn = ClientConnection::new(&mut stream, addr).unwrap();
+
+        let sent = conn.send_response(Response::login_play(&config, 1, &data, None));
+        tokio::time::timeout(Duration::from_secs(5), sent)
+            .await
+            .expect("Login (play) was not sent")
+            .unwrap();
+        let mut length = 0;
+        for pos in 0..VarInt::MAX_LEN {
+            let byte = client.read_u8().await.unwrap();
+            length |= ((byte & 0x7F) as usize) << (7 * pos);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        let mut packet = vec![0; length];
+        client.read_exact(&mut packet).await.unwrap();
+        assert_eq!(packet[0], 0x2B);
+    }
+}
@@ -0,0 +1,242 @@
+use super::{Tick, World};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tokio::task::AbortHandle;
+
+type OnceTask = Box<dyn FnOnce(&mut World, &mut Tick) + Send>;
+type RepeatingTask = Box<dyn FnMut(&mut World, &mut Tick) + Send>;
+
+enum Task {
+    Once(OnceTask),
+    Repeating { period: u64, run: RepeatingTask },
+}
+
+struct Scheduled {
+    task: Task,
+    handle: TaskHandle,
+}
+
+/// Cancels a scheduled task, it is kept by the scheduler until it would have run next.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+    /// Future of an async task.
+    abort: Option<AbortHandle>,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// Runs tasks on the game loop, with access to the world, after a delay in ticks.
+///
+/// Delays count from the current tick, a delay of `0` runs the task on the next tick like a delay of `1`.
+#[derive(Clone)]
+pub struct Scheduler {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Tick currently or last run by the game loop.
+    current_tick: AtomicU64,
+    next_id: AtomicU64,
+    /// Tasks by the tick they are due and their ID, to run them in the order they were scheduled.
+    tasks: Mutex<BTreeMap<(u64, u64), Scheduled>>,
+    runtime: Option<Handle>,
+}
+
+impl Scheduler {
+    /// Async tasks are spawned on the Tokio runtime the scheduler is created in.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                current_tick: AtomicU64::new(0),
+                next_id: AtomicU64::new(0),
+                tasks: Mutex::default(),
+                runtime: Handle::try_current().ok(),
+            }),
+        }
+    }
+
+    pub fn run_later(&self, delay: u64, task: impl FnOnce(&mut World, &mut Tick) + Send + 'static) -> TaskHandle {
+        let handle = self.new_handle();
+        self.insert(delay, Task::Once(Box::new(task)), handle.clone());
+        handle
+    }
+
+    /// Runs the task after `delay` ticks, then every `period` ticks until it is cancelled.
+    pub fn run_repeating(
+        &self,
+        delay: u64,
+        period: u64,
+        task: impl FnMut(&mut World, &mut Tick) + Send + 'static,
+    ) -> TaskHandle {
+        let handle = self.new_handle();
+        let task = Task::Repeating {
+            period: period.max(1),
+            run: Box::new(task),
+        };
+        self.insert(delay, task, handle.clone());
+        handle
+    }
+
+    /// Runs the future on the Tokio runtime, then passes its output to `then` on the next tick.
+    ///
+    /// Cancelling the task aborts the future or skips `then` if it already completed.
+    pub fn run_async<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+        then: impl FnOnce(T, &mut World, &mut Tick) + Send + 'static,
+    ) -> TaskHandle {
+        let runtime = self
+            .inner
+            .runtime
+            .as_ref()
+            .expect("Async tasks need a scheduler created in a Tokio runtime");
+
+        let mut handle = self.new_handle();
+        let callback = handle.clone();
+        let scheduler = self.clone();
+        let join = runtime.spawn(async move {
+            let output = future.await;
+            let task = Task::Once(Box::new(move |world, tick| then(output, world, tick)));
+            scheduler.insert(0, task, callback);
+        });
+        handle.abort = Some(join.abort_handle());
+        handle
+    }
+
+    /// Number of tasks waiting to run, including cancelled ones not yet dropped.
+    pub fn pending(&self) -> usize {
+        self.inner.tasks.lock().unwrap().len()
+    }
+
+    fn new_handle(&self) -> TaskHandle {
+        TaskHandle {
+            id: self.inner.next_id.fetch_add(1, Ordering::Relaxed),
+            cancelled: Arc::default(),
+            abort: None,
+        }
+    }
+
+    fn insert(&self, delay: u64, task: Task, handle: TaskHandle) {
+        let due = self.inner.current_tick.load(Ordering::Acquire) + delay.max(1);
+        self.insert_at(due, task, handle);
+    }
+
+    fn insert_at(&self, due: u64, task: Task, handle: TaskHandle) {
+        let scheduled = Scheduled { task, handle };
+        self.inner
+            .tasks
+            .lock()
+            .unwrap()
+            .insert((due, scheduled.handle.id), scheduled);
+    }
+
+    /// Runs the tasks due on the tick of the world. Tasks scheduled while running are due on a later tick.
+    pub(super) fn run_due(&self, world: &mut World, tick: &mut Tick) {
+        let now = world.tick;
+        self.inner.current_tick.store(now, Ordering::Release);
+
+        let due = {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            let later = tasks.split_off(&(now + 1, 0));
+            std::mem::replace(&mut *tasks, later)
+        };
+
+        for (_, Scheduled { task, handle }) in due {
+            if handle.is_cancelled() {
+                continue;
+            }
+            tick.watch.detail(format!("system scheduler, task {}", handle.id));
+            match task {
+                Task::Once(run) => run(world, tick),
+                Task::Repeating { period, mut run } => {
+                    run(world, tick);
+                    if !handle.is_cancelled() {
+                        self.insert_at(now + period, Task::Repeating { period, run }, handle);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::game::Outbound;
+    use crate::server::Server;
+    use crate::storage::MemoryStorage;
+    use std::time::Duration;
+
+    fn run_ticks(server: &Server, world: &mut World, ticks: u64) {
+        for _ in 0..ticks {
+            world.tick += 1;
+            let mut tick = Tick {
+                server,
+                outbound: Outbound::default(),
+                watch: server.watchdog.enter("tick"),
+            };
+            server.scheduler.run_due(world, &mut tick);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        let server = Server::new(ServerConfig::default(), Arc::new(MemoryStorage::new())).unwrap();
+        let scheduler = &server.scheduler;
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let later = log.clone();
+        scheduler.run_later(3, move |world, _| {
+            later.lock().unwrap().push(format!("later {}", world.tick))
+        });
+        let repeating = log.clone();
+        let every = scheduler.run_repeating(1, 2, move |world, _| {
+            repeating.lock().unwrap().push(format!("every {}", world.tick))
+        });
+        let cancelled = log.clone();
+        scheduler
+            .run_later(2, move |_, _| cancelled.lock().unwrap().push("cancelled".into()))
+            .cancel();
+
+        run_ticks(&server, &mut world, 4);
+        every.cancel();
+        run_ticks(&server, &mut world, 4);
+        assert_eq!(*log.lock().unwrap(), ["every 1", "later 3", "every 3"]);
+        assert_eq!(scheduler.pending(), 0);
+
+        let done = log.clone();
+        scheduler.run_async(async { 42 }, move |answer, world, _| {
+            done.lock().unwrap().push(format!("async {} {}", answer, world.tick))
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        run_ticks(&server, &mut world, 1);
+        assert_eq!(log.lock().unwrap().last().unwrap(), "async 42 9");
+    }
+}
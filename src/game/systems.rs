@@ -0,0 +1,236 @@
+use super::{player_data, PlayerAction, PlayerEntity, QueuedAction, Tick, World};
+use crate::plugin::{
+    BlockBreakEvent, BlockPlaceEvent, PlayerChatEvent, PlayerJoinEvent, PlayerMoveEvent, PlayerQuitEvent,
+};
+use crate::protocol::types::enums::{GameMode, PlayerActionStatus};
+use crate::protocol::types::{Position, TextComponent};
+use crate::server::SessionCommand;
+use std::time::Instant;
+use tracing::{debug, info};
+
+type System = fn(&mut World, &[QueuedAction], &mut Tick);
+
+/// Systems of a tick, in the order they run. Scheduled tasks run first, then players join first and leave last,
+/// so every other system sees the actions of players who are in the world. Players still online are saved last.
+pub const SYSTEMS: [(&str, System); 7] = [
+    ("scheduler", scheduler),
+    ("join", join),
+    ("movement", movement),
+    ("chat", chat),
+    ("blocks", blocks),
+    ("leave", leave),
+    ("autosave", autosave),
+];
+
+pub fn run(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for (name, system) in SYSTEMS {
+        tick.watch.detail(format!("system {}", name));
+        if tick.server.profiler.is_active() {
+            let start = Instant::now();
+            system(world, actions, tick);
+            tick.server.profiler.record_system(name, start.elapsed());
+        } else {
+            system(world, actions, tick);
+        }
+    }
+}
+
+fn scheduler(world: &mut World, _: &[QueuedAction], tick: &mut Tick) {
+    tick.server.scheduler.run_due(world, tick);
+}
+
+fn join(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for queued in actions {
+        let PlayerAction::Join { profile, data } = &queued.action else {
+            continue;
+        };
+
+        world.players.insert(
+            queued.entity_id,
+            PlayerEntity {
+                entity_id: queued.entity_id,
+                profile: profile.clone(),
+                on_ground: false,
+                data: data.as_ref().clone(),
+            },
+        );
+        tick.outbound.send(profile.uuid, SessionCommand::Teleport(data.location));
+
+        let join_message = TextComponent::new(format!("{} joined the game", profile.name)).color("yellow");
+        let event = tick.server.fire(PlayerJoinEvent {
+            profile: profile.clone(),
+            entity_id: queued.entity_id,
+            join_message: Some(join_message),
+        });
+        if let Some(message) = event.join_message {
+            tick.outbound.broadcast(SessionCommand::Message(message));
+        }
+    }
+}
+
+fn movement(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for queued in actions {
+        let PlayerAction::Move {
+            position,
+            rotation,
+            on_ground,
+        } = queued.action
+        else {
+            continue;
+        };
+        let Some(player) = world.players.get_mut(&queued.entity_id) else {
+            continue;
+        };
+
+        player.on_ground = on_ground;
+        let from = player.data.location;
+        let mut to = from;
+        if let Some((x, y, z)) = position {
+            (to.x, to.y, to.z) = (x, y, z);
+        }
+        if let Some((yaw, pitch)) = rotation {
+            (to.yaw, to.pitch) = (yaw, pitch);
+        }
+        if to == from {
+            continue;
+        }
+
+        let event = tick.server.fire(PlayerMoveEvent {
+            profile: player.profile.clone(),
+            from,
+            to,
+            cancelled: false,
+        });
+        if event.cancelled {
+            tick.outbound.send(player.profile.uuid, SessionCommand::Teleport(from));
+        } else {
+            player.data.location = event.to;
+            if event.to != to {
+                tick.outbound
+                    .send(player.profile.uuid, SessionCommand::Teleport(event.to));
+            }
+        }
+    }
+}
+
+fn chat(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for queued in actions {
+        let PlayerAction::Chat { message } = &queued.action else {
+            continue;
+        };
+        let Some(player) = world.players.get(&queued.entity_id) else {
+            continue;
+        };
+
+        let event = tick.server.fire(PlayerChatEvent {
+            profile: player.profile.clone(),
+            message: message.clone(),
+            cancelled: false,
+        });
+        if !event.cancelled {
+            info!(player = %event.profile.name, message = %event.message, "Chat");
+            let content = TextComponent::new(format!("<{}> {}", event.profile.name, event.message));
+            tick.outbound.broadcast(SessionCommand::Message(content));
+        }
+    }
+}
+
+fn blocks(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for queued in actions {
+        let Some(player) = world.players.get(&queued.entity_id) else {
+            continue;
+        };
+        // Creative players break blocks instantly, without finishing the digging
+        let creative = player.data.game_mode == GameMode::Creative;
+
+        let sequence = match queued.action {
+            PlayerAction::Dig {
+                status,
+                ref position,
+                sequence,
+            } => {
+                enter_chunk(tick, position);
+                let breaks = match status {
+                    PlayerActionStatus::FinishedDigging => true,
+                    PlayerActionStatus::StartedDigging => creative,
+                    _ => false,
+                };
+                if breaks {
+                    let event = tick.server.fire(BlockBreakEvent {
+                        profile: player.profile.clone(),
+                        position: position.clone(),
+                        cancelled: false,
+                    });
+                    debug!(player = %player.profile.name, position = ?event.position, cancelled = event.cancelled, "Block break");
+                }
+                sequence
+            }
+            PlayerAction::UseItemOn {
+                hand,
+                ref position,
+                face,
+                sequence,
+            } => {
+                enter_chunk(tick, position);
+                let event = tick.server.fire(BlockPlaceEvent {
+                    profile: player.profile.clone(),
+                    position: position.relative(face),
+                    against: position.clone(),
+                    face,
+                    hand,
+                    cancelled: false,
+                });
+                debug!(player = %player.profile.name, position = ?event.position, cancelled = event.cancelled, "Block place");
+                sequence
+            }
+            _ => continue,
+        };
+
+        //TODO Resend the blocks of cancelled changes once there is a world
+        tick.outbound
+            .send(player.profile.uuid, SessionCommand::AcknowledgeBlockChange(sequence));
+    }
+}
+
+/// Reports the chunk of the block to the watchdog.
+fn enter_chunk(tick: &Tick, position: &Position) {
+    tick.watch.detail(format!(
+        "system blocks, chunk {}, {}",
+        position.x() >> 4,
+        position.z() >> 4
+    ));
+}
+
+fn leave(world: &mut World, actions: &[QueuedAction], tick: &mut Tick) {
+    for queued in actions {
+        if !matches!(queued.action, PlayerAction::Leave) {
+            continue;
+        }
+        let Some(player) = world.players.remove(&queued.entity_id) else {
+            continue;
+        };
+        player_data::save_on_leave(tick.server, &player);
+
+        let quit_message = TextComponent::new(format!("{} left the game", player.profile.name)).color("yellow");
+        let event = tick.server.fire(PlayerQuitEvent {
+            profile: player.profile,
+            entity_id: player.entity_id,
+            quit_message: Some(quit_message),
+        });
+        if let Some(message) = event.quit_message {
+            tick.outbound.broadcast(SessionCommand::Message(message));
+        }
+    }
+}
+
+fn autosave(world: &mut World, _: &[QueuedAction], tick: &mut Tick) {
+    let interval = tick.server.config.storage.autosave_interval_secs * super::TPS as u64;
+    if interval == 0 || world.tick == 0 || !world.tick.is_multiple_of(interval) {
+        return;
+    }
+
+    for player in world.players.values() {
+        player_data::save(tick.server, player);
+    }
+    debug!(players = world.players.len(), "Saved online players");
+}
@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Ticks the averages are computed over, five seconds at full speed.
+const WINDOW: usize = 100;
+
+/// Durations and start times of the latest ticks.
+#[derive(Debug, Clone, Default)]
+pub struct TickTimings {
+    durations: VecDeque<Duration>,
+    starts: VecDeque<Instant>,
+    /// Ticks run since the server started.
+    pub ticks: u64,
+    /// Ticks dropped to recover from overload.
+    pub skipped: u64,
+}
+
+impl TickTimings {
+    pub fn record(&mut self, start: Instant, duration: Duration) {
+        if self.durations.len() == WINDOW {
+            self.durations.pop_front();
+            self.starts.pop_front();
+        }
+        self.durations.push_back(duration);
+        self.starts.push_back(start);
+        self.ticks += 1;
+    }
+
+    /// Average milliseconds per tick.
+    pub fn mspt(&self) -> f64 {
+        match self.durations.len() {
+            0 => 0.0,
+            len => self.durations.iter().sum::<Duration>().as_secs_f64() * 1000.0 / len as f64,
+        }
+    }
+
+    /// Slowest tick in the window, in milliseconds.
+    pub fn peak_mspt(&self) -> f64 {
+        self.durations
+            .iter()
+            .max()
+            .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0)
+    }
+
+    /// Ticks per second measured from the start times, capped at the target rate.
+    pub fn tps(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.starts.front(), self.starts.back()) else {
+            return 0.0;
+        };
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        match elapsed > 0.0 {
+            true => ((self.starts.len() - 1) as f64 / elapsed).min(super::TPS as f64),
+            false => super::TPS as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_averages() {
+        let mut timings = TickTimings::default();
+        let start = Instant::now();
+
+        // Ticks alternate between 10 and 30ms and start 100ms apart, as if overloaded
+        for tick in 0..(WINDOW as u32 + 10) {
+            let duration = Duration::from_millis(if tick % 2 == 0 { 10 } else { 30 });
+            timings.record(start + Duration::from_millis(100) * tick, duration);
+        }
+
+        assert_eq!(timings.ticks, WINDOW as u64 + 10);
+        assert!((timings.mspt() - 20.0).abs() < 1e-9);
+        assert!((timings.peak_mspt() - 30.0).abs() < 1e-9);
+        assert!((timings.tps() - 10.0).abs() < 1e-9);
+    }
+}
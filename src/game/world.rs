@@ -0,0 +1,27 @@
+use super::PlayerData;
+use crate::protocol::types::GameProfile;
+use std::collections::HashMap;
+
+/// Player in the world, owned by the game loop.
+#[derive(Debug, Clone)]
+pub struct PlayerEntity {
+    pub entity_id: i32,
+    pub profile: GameProfile,
+    pub on_ground: bool,
+    /// Saved state, including the location of the player.
+    pub data: PlayerData,
+}
+
+/// World and entity state, only touched from the game loop.
+#[derive(Debug, Default)]
+pub struct World {
+    /// Ticks run since the server started.
+    pub tick: u64,
+    pub players: HashMap<i32, PlayerEntity>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -1,2 +1,15 @@
+pub mod access;
+pub mod command;
+pub mod config;
 pub mod connection;
+pub mod diagnostics;
+pub mod game;
+pub mod metrics;
+pub mod plugin;
 pub mod protocol;
+pub mod proxy_protocol;
+pub mod query;
+pub mod rcon;
+pub mod registry;
+pub mod server;
+pub mod storage;
@@ -0,0 +1,212 @@
+use crate::protocol::types::VarInt;
+use crate::server::Server;
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::{Arc, LazyLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+/// Label of connections that have not sent their handshake yet.
+pub const HANDSHAKE_STATE: &str = "handshake";
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Prometheus metrics of the process, exposed on the `/metrics` endpoint.
+pub struct Metrics {
+    registry: Registry,
+    pub connections: IntGaugeVec,
+    pub packets: IntCounterVec,
+    pub bytes: IntCounterVec,
+    pub handshake_failures: IntCounterVec,
+    pub login_failures: IntCounterVec,
+    pub tick_duration: Histogram,
+    pub redis_latency: HistogramVec,
+    pub redis_connections: IntGaugeVec,
+    pub storage_available: IntGauge,
+    pub online_players: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("minecraft".into()), None).expect("Valid metrics prefix");
+
+        let metrics = Self {
+            connections: IntGaugeVec::new(Opts::new("connections", "Open client connections"), &["state"]).unwrap(),
+            packets: IntCounterVec::new(
+                Opts::new("packets_total", "Packets sent and received"),
+                &["direction", "state", "packet_id"],
+            )
+            .unwrap(),
+            bytes: IntCounterVec::new(
+                Opts::new("packet_bytes_total", "Packet bytes sent and received"),
+                &["direction", "state", "packet_id"],
+            )
+            .unwrap(),
+            handshake_failures: IntCounterVec::new(
+                Opts::new(
+                    "handshake_failures_total",
+                    "Connections rejected before the handshake completed",
+                ),
+                &["reason"],
+            )
+            .unwrap(),
+            login_failures: IntCounterVec::new(
+                Opts::new("login_failures_total", "Players refused during login"),
+                &["reason"],
+            )
+            .unwrap(),
+            tick_duration: Histogram::with_opts(
+                HistogramOpts::new("tick_duration_seconds", "Duration of a server tick")
+                    .buckets(exponential_buckets(0.001, 2.0, 10).unwrap()),
+            )
+            .unwrap(),
+            redis_latency: HistogramVec::new(
+                HistogramOpts::new("redis_command_duration_seconds", "Latency of Redis commands")
+                    .buckets(exponential_buckets(0.0005, 2.0, 12).unwrap()),
+                &["operation"],
+            )
+            .unwrap(),
+            redis_connections: IntGaugeVec::new(
+                Opts::new("redis_pool_connections", "Connections of the Redis pool"),
+                &["kind"],
+            )
+            .unwrap(),
+            storage_available: IntGauge::new("storage_available", "Whether the storage backend is reachable").unwrap(),
+            online_players: IntGauge::new("online_players", "Players in the Play state").unwrap(),
+            registry,
+        };
+
+        metrics
+            .registry
+            .register(Box::new(metrics.connections.clone()))
+            .unwrap();
+        metrics.registry.register(Box::new(metrics.packets.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bytes.clone())).unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.handshake_failures.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.login_failures.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.tick_duration.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.redis_latency.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.redis_connections.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.storage_available.clone()))
+            .unwrap();
+        metrics
+            .registry
+            .register(Box::new(metrics.online_players.clone()))
+            .unwrap();
+
+        metrics
+    }
+
+    pub fn packet_received(&self, state: &str, packet_id: VarInt, length: usize) {
+        self.record_packet("in", state, packet_id, length);
+    }
+
+    pub fn packet_sent(&self, state: &str, packet_id: VarInt, length: usize) {
+        self.record_packet("out", state, packet_id, length);
+    }
+
+    fn record_packet(&self, direction: &str, state: &str, packet_id: VarInt, length: usize) {
+        let packet_id = format!("0x{:02X}", packet_id);
+        let labels = [direction, state, packet_id.as_str()];
+        self.packets.with_label_values(&labels).inc();
+        self.bytes.with_label_values(&labels).inc_by(length as u64);
+    }
+
+    pub fn handshake_failed(&self, reason: &str) {
+        self.handshake_failures.with_label_values(&[reason]).inc();
+    }
+
+    pub fn login_failed(&self, reason: &str) {
+        self.login_failures.with_label_values(&[reason]).inc();
+    }
+
+    /// Metrics in the Prometheus text format, sampling the gauges read from the server.
+    pub fn render(&self, server: &Server) -> anyhow::Result<String> {
+        self.online_players.set(server.players.len() as i64);
+
+        self.storage_available.set(server.storage.is_available() as i64);
+        if let Some(pool) = server.storage.pool_status() {
+            self.redis_connections
+                .with_label_values(&["max"])
+                .set(pool.max_size as i64);
+            self.redis_connections
+                .with_label_values(&["open"])
+                .set(pool.size as i64);
+            self.redis_connections
+                .with_label_values(&["idle"])
+                .set(pool.available as i64);
+            self.redis_connections
+                .with_label_values(&["waiting"])
+                .set(pool.waiting as i64);
+        }
+
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+/// Serves `GET /metrics` until the server shuts down.
+pub async fn listen(server: Arc<Server>, listener: TcpListener) -> anyhow::Result<()> {
+    info!("Metrics listening on http://{}/metrics", listener.local_addr()?);
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = server.wait_for_shutdown() => return Ok(()),
+        };
+        let server = server.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_scrape(stream, &server).await {
+                warn!(peer = %addr, error = %e, "Metrics connection error");
+            }
+        });
+    }
+}
+
+async fn handle_scrape(mut stream: TcpStream, server: &Server) -> anyhow::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() > 8192 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request_line = request.split(|&byte| byte == b'\r').next().unwrap_or_default();
+    let (status, content_type, body) = match request_line.strip_prefix(b"GET /metrics") {
+        Some([] | [b' ', ..] | [b'?', ..]) => ("200 OK", prometheus::TEXT_FORMAT, METRICS.render(server)?),
+        _ => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+mod bus;
+mod events;
+mod wasm;
+
+use crate::server::Server;
+
+pub use bus::{EventBus, EventPriority};
+pub use events::{
+    BlockBreakEvent, BlockPlaceEvent, CommandEvent, Event, PlayerChatEvent, PlayerJoinEvent, PlayerMoveEvent,
+    PlayerPreLoginEvent, PlayerQuitEvent,
+};
+pub use wasm::{EventKind, WasmHost, ABI_VERSION};
+
+/// Extension compiled into the server binary and registered with `ServerBuilder::plugin`.
+///
+/// Plugins react to the game through the handlers they subscribe on the event bus.
+pub trait Plugin: Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    /// Subscribes the plugin's event handlers, called once while the server is built.
+    fn enable(&self, events: &mut EventBus);
+
+    /// Called once the server has stopped and every player is gone.
+    fn disable(&self, _server: &Server) {}
+}
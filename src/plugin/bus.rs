@@ -0,0 +1,152 @@
+use super::Event;
+use crate::server::Server;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Order in which handlers see an event, `Lowest` first.
+///
+/// Handlers with a higher priority run later and have the final say on the outcome of the event.
+/// `Monitor` handlers should only observe it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventPriority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    Highest,
+    Monitor,
+}
+
+type Handler = Box<dyn Fn(&Server, &mut dyn Any) + Send + Sync>;
+
+struct Subscription {
+    priority: EventPriority,
+    /// Plugin that subscribed the handler, reported by the watchdog and profiler.
+    owner: Arc<str>,
+    handler: Handler,
+}
+
+/// Handlers subscribed by plugins, grouped by event type.
+pub struct EventBus {
+    handlers: HashMap<TypeId, Vec<Subscription>>,
+    owner: Arc<str>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            owner: "server".into(),
+        }
+    }
+
+    /// Attributes the handlers subscribed from now on to the plugin.
+    pub fn set_owner(&mut self, plugin: &str) {
+        self.owner = plugin.into();
+    }
+
+    /// Handlers of the same priority run in the order they were subscribed.
+    pub fn subscribe<E: Event>(
+        &mut self,
+        priority: EventPriority,
+        handler: impl Fn(&Server, &mut E) + Send + Sync + 'static,
+    ) {
+        let handler: Handler = Box::new(move |server, event| {
+            if let Some(event) = event.downcast_mut::<E>() {
+                handler(server, event);
+            }
+        });
+
+        let handlers = self.handlers.entry(TypeId::of::<E>()).or_default();
+        let index = handlers.partition_point(|subscription| subscription.priority <= priority);
+        handlers.insert(
+            index,
+            Subscription {
+                priority,
+                owner: self.owner.clone(),
+                handler,
+            },
+        );
+    }
+
+    /// Runs every handler of the event and returns it, to check whether it was cancelled or altered.
+    pub fn fire<E: Event>(&self, server: &Server, mut event: E) -> E {
+        if let Some(handlers) = self.handlers.get(&TypeId::of::<E>()) {
+            for subscription in handlers {
+                let _watch = server.watchdog.enter(format!(
+                    "plugin {} handling {}",
+                    subscription.owner,
+                    short_type_name::<E>()
+                ));
+                if server.profiler.is_active() {
+                    let start = Instant::now();
+                    (subscription.handler)(server, &mut event);
+                    server.profiler.record_plugin(&subscription.owner, start.elapsed());
+                } else {
+                    (subscription.handler)(server, &mut event);
+                }
+            }
+        }
+        event
+    }
+
+    pub fn has_handlers<E: Event>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<E>())
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn short_type_name<E>() -> &'static str {
+    let name = std::any::type_name::<E>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::plugin::PlayerChatEvent;
+    use crate::protocol::types::GameProfile;
+    use crate::storage::MemoryStorage;
+    use uuid::Uuid;
+
+    fn chat(message: &str) -> PlayerChatEvent {
+        PlayerChatEvent {
+            profile: GameProfile::new(Uuid::nil(), "Notch"),
+            message: message.into(),
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn test_priority_and_cancellation() {
+        let server = Server::new(ServerConfig::default(), Arc::new(MemoryStorage::new())).unwrap();
+
+        let mut bus = EventBus::new();
+        bus.subscribe(EventPriority::Monitor, |_, event: &mut PlayerChatEvent| {
+            event.message.push_str(" monitor");
+        });
+        bus.subscribe(EventPriority::Lowest, |_, event: &mut PlayerChatEvent| {
+            event.message.push_str(" lowest");
+        });
+        bus.subscribe(EventPriority::Normal, |_, event: &mut PlayerChatEvent| {
+            event.cancelled = event.message.starts_with("spam");
+        });
+        bus.subscribe(EventPriority::Lowest, |_, event: &mut PlayerChatEvent| {
+            event.message.push_str(" second");
+        });
+
+        let event = bus.fire(&server, chat("hello"));
+        assert_eq!(event.message, "hello lowest second monitor");
+        assert!(!event.is_cancelled());
+
+        assert!(bus.fire(&server, chat("spam")).is_cancelled());
+    }
+}
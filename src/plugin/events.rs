@@ -0,0 +1,111 @@
+use crate::command::CommandSender;
+use crate::protocol::types::enums::{BlockFace, InteractionHand};
+use crate::protocol::types::{GameProfile, Location, Position, TextComponent};
+use std::any::Any;
+use std::net::SocketAddr;
+
+/// Something that happened on the server, passed to the handlers subscribed on the event bus.
+pub trait Event: Any + Send {
+    /// Whether a handler cancelled the event, so the server should not carry out the action.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! cancellable {
+    ($($event:ty),*) => {
+        $(
+            impl Event for $event {
+                fn is_cancelled(&self) -> bool {
+                    self.cancelled
+                }
+            }
+        )*
+    };
+}
+
+cancellable!(
+    PlayerPreLoginEvent,
+    PlayerChatEvent,
+    PlayerMoveEvent,
+    BlockBreakEvent,
+    BlockPlaceEvent,
+    CommandEvent
+);
+
+impl Event for PlayerJoinEvent {}
+impl Event for PlayerQuitEvent {}
+
+/// A player passed the access checks and is about to log in.
+#[derive(Debug, Clone)]
+pub struct PlayerPreLoginEvent {
+    pub profile: GameProfile,
+    pub addr: SocketAddr,
+    pub cancelled: bool,
+    /// Shown to the player when the login is cancelled.
+    pub kick_message: TextComponent,
+}
+
+/// A player entered the world.
+#[derive(Debug, Clone)]
+pub struct PlayerJoinEvent {
+    pub profile: GameProfile,
+    pub entity_id: i32,
+    /// Broadcast to every player, none if unset.
+    pub join_message: Option<TextComponent>,
+}
+
+/// A player who was in the world disconnected.
+#[derive(Debug, Clone)]
+pub struct PlayerQuitEvent {
+    pub profile: GameProfile,
+    pub entity_id: i32,
+    /// Broadcast to the remaining players, none if unset.
+    pub quit_message: Option<TextComponent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerChatEvent {
+    pub profile: GameProfile,
+    pub message: String,
+    pub cancelled: bool,
+}
+
+/// A player moved or turned. A cancelled move sends the player back to `from`, an altered `to` teleports them there.
+#[derive(Debug, Clone)]
+pub struct PlayerMoveEvent {
+    pub profile: GameProfile,
+    pub from: Location,
+    pub to: Location,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockBreakEvent {
+    pub profile: GameProfile,
+    pub position: Position,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockPlaceEvent {
+    pub profile: GameProfile,
+    /// Position of the new block, next to the clicked `face` of the block the player aimed at.
+    pub position: Position,
+    pub against: Position,
+    pub face: BlockFace,
+    pub hand: InteractionHand,
+    pub cancelled: bool,
+}
+
+/// A command is about to run, from a player, the console or RCON.
+///
+/// Plugins handle their own commands by cancelling the event and setting the `response`.
+#[derive(Debug, Clone)]
+pub struct CommandEvent {
+    pub sender: CommandSender,
+    pub line: String,
+    pub cancelled: bool,
+    /// Output sent back to the sender of a cancelled command.
+    pub response: Option<String>,
+}
@@ -0,0 +1,920 @@
+//! Sandboxed WebAssembly plugins, loaded from the plugins directory and reloadable at runtime.
+//!
+//! Every plugin runs in its own store with a fuel budget per call and a memory limit, so a plugin stuck in a
+//! loop or allocating without bounds traps instead of stalling the server.
+//!
+//! # ABI version 1
+//!
+//! A plugin exports:
+//! - `memory`
+//! - `mc_abi_version() -> i32`, returning `1`
+//! - `mc_alloc(len: i32) -> i32`, a buffer of `len` bytes the host copies event data into
+//! - `mc_on_event(kind: i32, ptr: i32, len: i32) -> i32`, called with the event as JSON, returns `1` to cancel it
+//! - `mc_on_enable()` and `mc_on_disable()`, both optional
+//! - `mc_on_task(task: i32)`, only required to schedule tasks
+//!
+//! and may import from the `minecraft_v1` module:
+//! - `subscribe(kind: i32, priority: i32)`, only allowed during `mc_on_enable`
+//! - `log(level: i32, ptr: i32, len: i32)`, from `0` (error) to `3` (debug)
+//! - `send_message(player_ptr: i32, player_len: i32, text_ptr: i32, text_len: i32, overlay: i32)`
+//! - `broadcast(text_ptr: i32, text_len: i32)`
+//! - `kick(player_ptr: i32, player_len: i32, reason_ptr: i32, reason_len: i32)`
+//! - `run_command(ptr: i32, len: i32)`, run with the plugin as sender
+//! - `schedule(task: i32, delay: i32, period: i32)`, calls `mc_on_task(task)` on the game loop after `delay` ticks,
+//!   then every `period` ticks if it is positive. Scheduling a task again replaces it.
+//! - `cancel_task(task: i32)`
+//!
+//! Strings are UTF-8, players are referred to by name. Event kinds are numbered in the order of `EventKind`,
+//! priorities in the order of `EventPriority`. Messages, kicks, commands and tasks take effect once the call
+//! returned. Tasks are cancelled when the plugin is unloaded or reloaded.
+
+use super::{
+    BlockBreakEvent, BlockPlaceEvent, CommandEvent, Event, EventBus, EventPriority, PlayerChatEvent, PlayerJoinEvent,
+    PlayerMoveEvent, PlayerPreLoginEvent, PlayerQuitEvent, Plugin,
+};
+use crate::command::{self, CommandSender};
+use crate::config::PluginsConfig;
+use crate::game::TaskHandle;
+use crate::protocol::types::{GameProfile, Position, TextComponent};
+use crate::server::{Server, SessionCommand};
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info, warn};
+use wasmi::{
+    Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+pub const ABI_VERSION: i32 = 1;
+const HOST_MODULE: &str = "minecraft_v1";
+const MAX_STRING_LEN: usize = 64 * 1024;
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+const PRIORITIES: [EventPriority; 6] = [
+    EventPriority::Lowest,
+    EventPriority::Low,
+    EventPriority::Normal,
+    EventPriority::High,
+    EventPriority::Highest,
+    EventPriority::Monitor,
+];
+
+/// Events a WebAssembly plugin can subscribe to, numbered in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    PlayerPreLogin,
+    PlayerJoin,
+    PlayerQuit,
+    PlayerChat,
+    BlockBreak,
+    BlockPlace,
+    PlayerMove,
+    Command,
+}
+
+impl TryFrom<i32> for EventKind {
+    type Error = String;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EventKind::PlayerPreLogin),
+            1 => Ok(EventKind::PlayerJoin),
+            2 => Ok(EventKind::PlayerQuit),
+            3 => Ok(EventKind::PlayerChat),
+            4 => Ok(EventKind::BlockBreak),
+            5 => Ok(EventKind::BlockPlace),
+            6 => Ok(EventKind::PlayerMove),
+            7 => Ok(EventKind::Command),
+            _ => Err(format!("Unknown event kind: {}", value)),
+        }
+    }
+}
+
+/// Event passed to WebAssembly plugins as JSON.
+trait WasmEvent: Event {
+    const KIND: EventKind;
+
+    fn to_json(&self) -> Value;
+
+    /// Applies a cancellation returned by a plugin, ignored by events that can't be cancelled.
+    fn cancel(&mut self) {}
+}
+
+impl WasmEvent for PlayerPreLoginEvent {
+    const KIND: EventKind = EventKind::PlayerPreLogin;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "player": player_json(&self.profile),
+            "address": self.addr.ip().to_string(),
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+impl WasmEvent for PlayerJoinEvent {
+    const KIND: EventKind = EventKind::PlayerJoin;
+
+    fn to_json(&self) -> Value {
+        json!({ "player": player_json(&self.profile), "entity_id": self.entity_id })
+    }
+}
+
+impl WasmEvent for PlayerQuitEvent {
+    const KIND: EventKind = EventKind::PlayerQuit;
+
+    fn to_json(&self) -> Value {
+        json!({ "player": player_json(&self.profile), "entity_id": self.entity_id })
+    }
+}
+
+impl WasmEvent for PlayerChatEvent {
+    const KIND: EventKind = EventKind::PlayerChat;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "player": player_json(&self.profile),
+            "message": self.message,
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+impl WasmEvent for BlockBreakEvent {
+    const KIND: EventKind = EventKind::BlockBreak;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "player": player_json(&self.profile),
+            "position": position_json(&self.position),
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+impl WasmEvent for BlockPlaceEvent {
+    const KIND: EventKind = EventKind::BlockPlace;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "player": player_json(&self.profile),
+            "position": position_json(&self.position),
+            "against": position_json(&self.against),
+            "face": format!("{:?}", self.face).to_lowercase(),
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+impl WasmEvent for PlayerMoveEvent {
+    const KIND: EventKind = EventKind::PlayerMove;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "player": player_json(&self.profile),
+            "from": self.from,
+            "to": self.to,
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+impl WasmEvent for CommandEvent {
+    const KIND: EventKind = EventKind::Command;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "sender": self.sender.name(),
+            "line": self.line,
+            "cancelled": self.cancelled,
+        })
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
+
+fn player_json(profile: &GameProfile) -> Value {
+    json!({ "name": profile.name, "uuid": profile.uuid.to_string() })
+}
+
+fn position_json(position: &Position) -> Value {
+    json!({ "x": position.x(), "y": position.y(), "z": position.z() })
+}
+
+/// Effect requested by a plugin, carried out once its call returned.
+#[derive(Debug)]
+enum Action {
+    Message {
+        player: String,
+        content: TextComponent,
+        overlay: bool,
+    },
+    Broadcast(TextComponent),
+    Kick {
+        player: String,
+        reason: TextComponent,
+    },
+    Command(String),
+    Schedule {
+        task: i32,
+        delay: u64,
+        period: u64,
+    },
+    CancelTask(i32),
+}
+
+/// Data of a plugin's store, reachable from the host functions.
+struct HostState {
+    name: String,
+    limits: StoreLimits,
+    /// Set while `mc_on_enable` runs, the only time subscribing is allowed.
+    enabling: bool,
+    subscriptions: Vec<(EventKind, EventPriority)>,
+    actions: Vec<Action>,
+}
+
+/// Engine and host functions shared by every plugin.
+struct Runtime {
+    engine: Engine,
+    linker: Linker<HostState>,
+    fuel: u64,
+    max_memory: usize,
+}
+
+impl Runtime {
+    fn new(config: &PluginsConfig) -> anyhow::Result<Self> {
+        let mut wasm_config = Config::default();
+        wasm_config.consume_fuel(true);
+        let engine = Engine::new(&wasm_config);
+
+        let mut linker = Linker::new(&engine);
+        define_host_functions(&mut linker)?;
+
+        Ok(Self {
+            engine,
+            linker,
+            fuel: config.fuel,
+            max_memory: config.max_memory_mb * 1024 * 1024,
+        })
+    }
+}
+
+fn define_host_functions(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "subscribe",
+        |mut caller: Caller<'_, HostState>, kind: i32, priority: i32| -> Result<(), wasmi::Error> {
+            let state = caller.data_mut();
+            if !state.enabling {
+                return Err(wasmi::Error::new("subscribe is only allowed in mc_on_enable"));
+            }
+            let kind = EventKind::try_from(kind).map_err(wasmi::Error::new)?;
+            let Some(&priority) = usize::try_from(priority).ok().and_then(|index| PRIORITIES.get(index)) else {
+                return Err(wasmi::Error::new(format!("Unknown event priority: {}", priority)));
+            };
+            state.subscriptions.push((kind, priority));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let message = read_str(&caller, ptr, len)?;
+            let plugin = caller.data().name.as_str();
+            match level {
+                0 => error!(plugin, "{}", message),
+                1 => warn!(plugin, "{}", message),
+                2 => info!(plugin, "{}", message),
+                _ => debug!(plugin, "{}", message),
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "send_message",
+        |mut caller: Caller<'_, HostState>,
+         player_ptr: i32,
+         player_len: i32,
+         text_ptr: i32,
+         text_len: i32,
+         overlay: i32|
+         -> Result<(), wasmi::Error> {
+            let action = Action::Message {
+                player: read_str(&caller, player_ptr, player_len)?,
+                content: TextComponent::new(read_str(&caller, text_ptr, text_len)?),
+                overlay: overlay != 0,
+            };
+            caller.data_mut().actions.push(action);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "broadcast",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let content = TextComponent::new(read_str(&caller, ptr, len)?);
+            caller.data_mut().actions.push(Action::Broadcast(content));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "kick",
+        |mut caller: Caller<'_, HostState>,
+         player_ptr: i32,
+         player_len: i32,
+         reason_ptr: i32,
+         reason_len: i32|
+         -> Result<(), wasmi::Error> {
+            let action = Action::Kick {
+                player: read_str(&caller, player_ptr, player_len)?,
+                reason: TextComponent::new(read_str(&caller, reason_ptr, reason_len)?),
+            };
+            caller.data_mut().actions.push(action);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "run_command",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let line = read_str(&caller, ptr, len)?;
+            caller.data_mut().actions.push(Action::Command(line));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "schedule",
+        |mut caller: Caller<'_, HostState>, task: i32, delay: i32, period: i32| -> Result<(), wasmi::Error> {
+            let (Ok(delay), Ok(period)) = (u64::try_from(delay), u64::try_from(period)) else {
+                return Err(wasmi::Error::new("Task delay and period must not be negative"));
+            };
+            caller.data_mut().actions.push(Action::Schedule { task, delay, period });
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "cancel_task",
+        |mut caller: Caller<'_, HostState>, task: i32| {
+            caller.data_mut().actions.push(Action::CancelTask(task));
+        },
+    )?;
+    Ok(())
+}
+
+fn read_str(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    let Some(len) = usize::try_from(len).ok().filter(|&len| len <= MAX_STRING_LEN) else {
+        return Err(wasmi::Error::new(format!("Invalid string length: {}", len)));
+    };
+    let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+        return Err(wasmi::Error::new("Missing export 'memory'"));
+    };
+
+    let mut buf = vec![0; len];
+    memory
+        .read(caller, ptr as u32 as usize, &mut buf)
+        .map_err(|e| wasmi::Error::new(e.to_string()))?;
+    String::from_utf8(buf).map_err(|e| wasmi::Error::new(e.to_string()))
+}
+
+/// Instance of a loaded plugin.
+struct WasmPlugin {
+    name: String,
+    fuel: u64,
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_event: TypedFunc<(i32, i32, i32), i32>,
+    on_disable: Option<TypedFunc<(), ()>>,
+    on_task: Option<TypedFunc<i32, ()>>,
+}
+
+impl WasmPlugin {
+    fn instantiate(runtime: &Runtime, name: &str, wasm: &[u8]) -> anyhow::Result<Self> {
+        let module = Module::new(&runtime.engine, wasm)?;
+        let state = HostState {
+            name: name.to_string(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(runtime.max_memory)
+                .instances(1)
+                .memories(1)
+                .build(),
+            enabling: false,
+            subscriptions: Vec::new(),
+            actions: Vec::new(),
+        };
+        let mut store = Store::new(&runtime.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(runtime.fuel)?;
+
+        let instance = runtime.linker.instantiate_and_start(&mut store, &module)?;
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "mc_abi_version")
+            .context("Missing export 'mc_abi_version'")?
+            .call(&mut store, ())?;
+        if version != ABI_VERSION {
+            bail!("Unsupported ABI version {}, expected {}", version, ABI_VERSION);
+        }
+
+        let mut plugin = Self {
+            name: name.to_string(),
+            fuel: runtime.fuel,
+            memory: instance
+                .get_memory(&store, "memory")
+                .context("Missing export 'memory'")?,
+            alloc: instance
+                .get_typed_func(&store, "mc_alloc")
+                .context("Missing export 'mc_alloc'")?,
+            on_event: instance
+                .get_typed_func(&store, "mc_on_event")
+                .context("Missing export 'mc_on_event'")?,
+            on_disable: instance.get_typed_func(&store, "mc_on_disable").ok(),
+            on_task: instance.get_typed_func(&store, "mc_on_task").ok(),
+            store,
+        };
+
+        if let Ok(on_enable) = instance.get_typed_func::<(), ()>(&plugin.store, "mc_on_enable") {
+            plugin.store.data_mut().enabling = true;
+            plugin.store.set_fuel(plugin.fuel)?;
+            let result = on_enable.call(&mut plugin.store, ());
+            plugin.store.data_mut().enabling = false;
+            result.context("mc_on_enable failed")?;
+        }
+
+        Ok(plugin)
+    }
+
+    fn is_subscribed(&self, kind: EventKind, priority: EventPriority) -> bool {
+        self.store.data().subscriptions.contains(&(kind, priority))
+    }
+
+    /// Passes the event JSON to the plugin, returns whether it cancelled the event.
+    fn call_event(&mut self, kind: EventKind, event: &[u8]) -> anyhow::Result<bool> {
+        self.store.set_fuel(self.fuel)?;
+        let len = i32::try_from(event.len())?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory.write(&mut self.store, ptr as u32 as usize, event)?;
+
+        Ok(self.on_event.call(&mut self.store, (kind as i32, ptr, len))? == 1)
+    }
+
+    fn call_task(&mut self, task: i32) -> anyhow::Result<()> {
+        let on_task = self.on_task.context("Missing export 'mc_on_task'")?;
+        self.store.set_fuel(self.fuel)?;
+        on_task.call(&mut self.store, task)?;
+        Ok(())
+    }
+
+    fn take_actions(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.store.data_mut().actions)
+    }
+
+    fn disable(&mut self) {
+        let Some(on_disable) = self.on_disable else {
+            return;
+        };
+        if let Err(e) = self
+            .store
+            .set_fuel(self.fuel)
+            .and_then(|_| on_disable.call(&mut self.store, ()))
+        {
+            warn!(plugin = %self.name, error = %e, "mc_on_disable failed");
+        }
+    }
+}
+
+/// Loads WebAssembly plugins and bridges them to the event bus, registered as a native plugin.
+#[derive(Clone)]
+pub struct WasmHost {
+    inner: Arc<HostInner>,
+}
+
+struct HostInner {
+    runtime: Runtime,
+    directory: PathBuf,
+    watch: bool,
+    plugins: RwLock<Vec<Arc<Mutex<WasmPlugin>>>>,
+    /// Modification time of each plugin file when it was last loaded, successfully or not.
+    loaded: Mutex<HashMap<String, Option<SystemTime>>>,
+    commands: mpsc::UnboundedSender<(String, String)>,
+    command_queue: Mutex<Option<mpsc::UnboundedReceiver<(String, String)>>>,
+    /// Scheduled tasks by plugin and task number.
+    tasks: Mutex<HashMap<(String, i32), TaskHandle>>,
+}
+
+impl WasmHost {
+    /// Loads every `*.wasm` file of the plugins directory, logging the ones that fail to load.
+    pub fn new(config: &PluginsConfig) -> anyhow::Result<Self> {
+        let (commands, command_queue) = mpsc::unbounded_channel();
+        let host = Self {
+            inner: Arc::new(HostInner {
+                runtime: Runtime::new(config)?,
+                directory: config.directory.clone(),
+                watch: config.watch,
+                plugins: RwLock::default(),
+                loaded: Mutex::default(),
+                commands,
+                command_queue: Mutex::new(Some(command_queue)),
+                tasks: Mutex::default(),
+            }),
+        };
+        host.sync_directory(|_| false);
+        Ok(host)
+    }
+
+    /// Instantiates a plugin, replacing the loaded plugin of the same name only once the new one is enabled.
+    pub fn load(&self, name: &str, wasm: &[u8]) -> anyhow::Result<()> {
+        let plugin = WasmPlugin::instantiate(&self.inner.runtime, name, wasm)?;
+
+        let mut plugins = self.inner.plugins.write().unwrap();
+        let plugin = Arc::new(Mutex::new(plugin));
+        match plugins.iter_mut().find(|loaded| loaded.lock().unwrap().name == name) {
+            Some(loaded) => {
+                std::mem::replace(loaded, plugin).lock().unwrap().disable();
+                self.cancel_tasks(name);
+            }
+            None => plugins.push(plugin),
+        }
+        Ok(())
+    }
+
+    pub fn unload(&self, name: &str) -> bool {
+        let mut plugins = self.inner.plugins.write().unwrap();
+        let Some(index) = plugins.iter().position(|plugin| plugin.lock().unwrap().name == name) else {
+            return false;
+        };
+        plugins.remove(index).lock().unwrap().disable();
+        self.cancel_tasks(name);
+        true
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let plugins = self.inner.plugins.read().unwrap();
+        plugins
+            .iter()
+            .map(|plugin| plugin.lock().unwrap().name.clone())
+            .collect()
+    }
+
+    fn cancel_tasks(&self, plugin: &str) {
+        self.inner.tasks.lock().unwrap().retain(|(name, _), handle| {
+            if name == plugin {
+                handle.cancel();
+            }
+            name != plugin
+        });
+    }
+
+    /// Loads new and changed plugin files, and those selected by `reload`, then unloads the ones removed.
+    ///
+    /// Returns the names of the plugins that changed.
+    fn sync_directory(&self, reload: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut files = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&self.inner.directory) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str())
+                    && path.extension().is_some_and(|extension| extension == "wasm")
+                {
+                    let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok();
+                    files.insert(name.to_string(), (path.clone(), modified));
+                }
+            }
+        }
+
+        let mut changed = Vec::new();
+        let mut loaded = self.inner.loaded.lock().unwrap();
+        for (name, (path, modified)) in &files {
+            if loaded.get(name) == Some(modified) && !reload(name) {
+                continue;
+            }
+            loaded.insert(name.clone(), *modified);
+
+            match std::fs::read(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|wasm| self.load(name, &wasm))
+            {
+                Ok(()) => {
+                    info!(plugin = %name, "Loaded WebAssembly plugin");
+                    changed.push(name.clone());
+                }
+                Err(e) => error!(plugin = %name, error = %e, "Failed to load WebAssembly plugin"),
+            }
+        }
+
+        loaded.retain(|name, _| files.contains_key(name));
+        for name in self.names() {
+            if !files.contains_key(&name) && self.unload(&name) {
+                info!(plugin = %name, "Unloaded WebAssembly plugin");
+                changed.push(name);
+            }
+        }
+        changed
+    }
+
+    /// Watches the plugins directory and runs the commands of plugins, until the server shuts down.
+    pub async fn run(self, server: Arc<Server>) {
+        let Some(mut commands) = self.inner.command_queue.lock().unwrap().take() else {
+            return;
+        };
+        let mut watch = tokio::time::interval(WATCH_INTERVAL);
+        watch.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        self.apply_pending(&server);
+
+        loop {
+            tokio::select! {
+                _ = watch.tick(), if self.inner.watch => {
+                    self.sync_directory(|_| false);
+                    self.apply_pending(&server);
+                }
+                Some((plugin, line)) = commands.recv() => {
+                    let output = command::execute(&server, &CommandSender::Plugin(plugin.clone()), &line).await;
+                    debug!(plugin, command = %line, output, "Plugin command");
+                }
+                _ = server.wait_for_shutdown() => return,
+            }
+        }
+    }
+
+    fn subscribe<E: WasmEvent>(&self, events: &mut EventBus) {
+        for priority in PRIORITIES {
+            let host = self.clone();
+            events.subscribe(priority, move |server, event: &mut E| {
+                host.dispatch(server, priority, event)
+            });
+        }
+    }
+
+    fn dispatch<E: WasmEvent>(&self, server: &Server, priority: EventPriority, event: &mut E) {
+        let plugins = self.inner.plugins.read().unwrap();
+        for plugin in plugins.iter() {
+            let mut plugin = plugin.lock().unwrap();
+            if !plugin.is_subscribed(E::KIND, priority) {
+                continue;
+            }
+
+            let _watch = server
+                .watchdog
+                .enter(format!("wasm plugin {} handling {:?}", plugin.name, E::KIND));
+            let start = Instant::now();
+            match plugin.call_event(E::KIND, event.to_json().to_string().as_bytes()) {
+                Ok(true) => event.cancel(),
+                Ok(false) => {}
+                Err(e) => warn!(plugin = %plugin.name, error = %e, "Plugin failed to handle event"),
+            }
+            if server.profiler.is_active() {
+                server
+                    .profiler
+                    .record_plugin(&format!("wasm/{}", plugin.name), start.elapsed());
+            }
+
+            let actions = plugin.take_actions();
+            self.apply(server, &plugin.name, actions);
+        }
+    }
+
+    /// Applies the actions plugins requested while being enabled, before the server was running.
+    fn apply_pending(&self, server: &Server) {
+        let plugins = self.inner.plugins.read().unwrap();
+        for plugin in plugins.iter() {
+            let mut plugin = plugin.lock().unwrap();
+            let actions = plugin.take_actions();
+            self.apply(server, &plugin.name, actions);
+        }
+    }
+
+    fn run_task(&self, server: &Server, name: &str, task: i32, repeating: bool) {
+        if !repeating {
+            self.inner.tasks.lock().unwrap().remove(&(name.to_string(), task));
+        }
+        let plugins = self.inner.plugins.read().unwrap();
+        let Some(plugin) = plugins.iter().find(|plugin| plugin.lock().unwrap().name == name) else {
+            return;
+        };
+
+        let mut plugin = plugin.lock().unwrap();
+        let _watch = server
+            .watchdog
+            .enter(format!("wasm plugin {} running task {}", name, task));
+        if let Err(e) = plugin.call_task(task) {
+            warn!(plugin = %name, task, error = %e, "Plugin task failed");
+        }
+        let actions = plugin.take_actions();
+        self.apply(server, name, actions);
+    }
+
+    fn apply(&self, server: &Server, plugin: &str, actions: Vec<Action>) {
+        for action in actions {
+            match action {
+                Action::Message {
+                    player,
+                    content,
+                    overlay,
+                } => {
+                    if let Some(profile) = server.sessions.find_by_name(&player) {
+                        let command = match overlay {
+                            true => SessionCommand::ActionBar(content),
+                            false => SessionCommand::Message(content),
+                        };
+                        server.sessions.send(&profile.uuid, command);
+                    }
+                }
+                Action::Broadcast(content) => {
+                    server.sessions.broadcast(|| SessionCommand::Message(content.clone()));
+                }
+                Action::Kick { player, reason } => {
+                    if let Some(profile) = server.sessions.find_by_name(&player) {
+                        server.sessions.send(&profile.uuid, SessionCommand::Disconnect(reason));
+                    }
+                }
+                Action::Command(line) => {
+                    let _ = self.inner.commands.send((plugin.to_string(), line));
+                }
+                Action::Schedule { task, delay, period } => {
+                    let host = self.clone();
+                    let name = plugin.to_string();
+                    let handle = match period {
+                        0 => server
+                            .scheduler
+                            .run_later(delay, move |_, tick| host.run_task(tick.server, &name, task, false)),
+                        period => server.scheduler.run_repeating(delay, period, move |_, tick| {
+                            host.run_task(tick.server, &name, task, true)
+                        }),
+                    };
+                    let previous = self
+                        .inner
+                        .tasks
+                        .lock()
+                        .unwrap()
+                        .insert((plugin.to_string(), task), handle);
+                    if let Some(previous) = previous {
+                        previous.cancel();
+                    }
+                }
+                Action::CancelTask(task) => {
+                    if let Some(handle) = self.inner.tasks.lock().unwrap().remove(&(plugin.to_string(), task)) {
+                        handle.cancel();
+                    }
+                }
+            }
+        }
+    }
+
+    /// `plugins` lists the loaded plugins, `plugins reload [plugin]` reloads them from the directory.
+    fn handle_command(&self, server: &Server, event: &mut CommandEvent) {
+        let line = event.line.trim();
+        let mut args = line.strip_prefix('/').unwrap_or(line).split_whitespace();
+        if args.next() != Some("plugins") {
+            return;
+        }
+
+        let response = match (args.next(), args.next()) {
+            (None, _) => {
+                let names = self.names();
+                format!("There are {} WebAssembly plugin(s): {}", names.len(), names.join(", "))
+            }
+            (Some("reload"), name) => {
+                let changed = self.sync_directory(|plugin| name.is_none_or(|name| name == plugin));
+                self.apply_pending(server);
+                format!("Reloaded {} plugin(s): {}", changed.len(), changed.join(", "))
+            }
+            _ => "Usage: plugins [reload [plugin]]".into(),
+        };
+        event.cancelled = true;
+        event.response = Some(response);
+    }
+}
+
+impl Plugin for WasmHost {
+    fn name(&self) -> &str {
+        "wasm"
+    }
+
+    fn enable(&self, events: &mut EventBus) {
+        let host = self.clone();
+        events.subscribe(EventPriority::Lowest, move |server, event: &mut CommandEvent| {
+            host.handle_command(server, event)
+        });
+
+        self.subscribe::<PlayerPreLoginEvent>(events);
+        self.subscribe::<PlayerJoinEvent>(events);
+        self.subscribe::<PlayerQuitEvent>(events);
+        self.subscribe::<PlayerChatEvent>(events);
+        self.subscribe::<BlockBreakEvent>(events);
+        self.subscribe::<BlockPlaceEvent>(events);
+        self.subscribe::<PlayerMoveEvent>(events);
+        self.subscribe::<CommandEvent>(events);
+    }
+
+    fn disable(&self, _server: &Server) {
+        for (_, handle) in self.inner.tasks.lock().unwrap().drain() {
+            handle.cancel();
+        }
+        for plugin in self.inner.plugins.write().unwrap().drain(..) {
+            plugin.lock().unwrap().disable();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::storage::MemoryStorage;
+    use uuid::Uuid;
+
+    const CANCEL_CHAT: &str = r#"
+        (module
+            (import "minecraft_v1" "subscribe" (func $subscribe (param i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "mc_abi_version") (result i32) i32.const 1)
+            (func (export "mc_alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "mc_on_enable") (call $subscribe (i32.const 3) (i32.const 2)))
+            (func (export "mc_on_event") (param i32 i32 i32) (result i32) i32.const 1))
+    "#;
+
+    fn host() -> WasmHost {
+        let config = PluginsConfig {
+            directory: "missing-plugins-directory".into(),
+            fuel: 100_000,
+            ..Default::default()
+        };
+        WasmHost::new(&config).unwrap()
+    }
+
+    fn fire_chat(host: &WasmHost) -> PlayerChatEvent {
+        let server = Server::new(ServerConfig::default(), Arc::new(MemoryStorage::new())).unwrap();
+        let mut events = EventBus::new();
+        host.enable(&mut events);
+
+        events.fire(
+            &server,
+            PlayerChatEvent {
+                profile: GameProfile::new(Uuid::nil(), "Notch"),
+                message: "hello".into(),
+                cancelled: false,
+            },
+        )
+    }
+
+    #[test]
+    fn test_cancel_and_reload() {
+        let host = host();
+        host.load("chat", CANCEL_CHAT.as_bytes()).unwrap();
+        assert!(fire_chat(&host).cancelled);
+
+        // A reload that fails keeps the running plugin
+        assert!(host.load("chat", b"(module)").is_err());
+        assert!(fire_chat(&host).cancelled);
+
+        let passive = CANCEL_CHAT.replace("(i32.const 3)", "(i32.const 1)");
+        host.load("chat", passive.as_bytes()).unwrap();
+        assert!(!fire_chat(&host).cancelled);
+        assert_eq!(host.names(), vec!["chat"]);
+    }
+
+    #[test]
+    fn test_limits() {
+        let host = host();
+
+        let endless = CANCEL_CHAT.replace("(result i32) i32.const 1))", "(result i32) (loop br 0) i32.const 1))");
+        host.load("endless", endless.as_bytes()).unwrap();
+        assert!(!fire_chat(&host).cancelled);
+
+        let oversized = CANCEL_CHAT.replace("(memory (export \"memory\") 1)", "(memory (export \"memory\") 1024)");
+        assert!(host.load("oversized", oversized.as_bytes()).is_err());
+
+        let outdated = CANCEL_CHAT.replacen("(result i32) i32.const 1)", "(result i32) i32.const 2)", 1);
+        assert!(host.load("outdated", outdated.as_bytes()).is_err());
+    }
+}
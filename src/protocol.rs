@@ -3,5 +3,9 @@ mod reader;
 pub mod types;
 mod writer;
 
-pub use reader::ProtocolReader;
+pub use reader::{ConnectionClosed, ProtocolReader};
 pub use writer::ProtocolWriter;
+
+/// Minecraft version the server speaks.
+pub const VERSION_NAME: &str = "1.21.5";
+pub const PROTOCOL_VERSION: i32 = 770;
@@ -1,7 +1,7 @@
-use crate::protocol::types::{ReadBuffer, VarInt, WriteBuffer};
-use bytes::{BufMut, Bytes, BytesMut};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::tcp::{ReadHalf, WriteHalf};
+use crate::protocol::types::{ReadBuffer, VarInt, VarIntErr, WriteBuffer};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::WriteHalf;
 
 pub struct Packet {
     pub length: usize,
@@ -16,16 +16,33 @@ impl Packet {
         Packet { length, id, data }
     }
 
-    pub async fn read(stream: &mut ReadHalf<'_>) -> anyhow::Result<Self> {
-        let length = VarInt::read_stream(stream).await?.into();
-
-        let mut data = BytesMut::zeroed(length);
-        stream.read_exact(&mut data).await?;
-
-        let mut data: Bytes = data.into();
+    /// Splits the next complete packet off the buffer, or returns `None` if more bytes are needed.
+    pub fn decode(buf: &mut BytesMut) -> anyhow::Result<Option<Self>> {
+        let mut length: usize = 0;
+        let mut length_size = 0;
+
+        for (pos, byte) in buf.iter().take(VarInt::MAX_LEN).enumerate() {
+            length |= ((byte & 0x7F) as usize) << (7 * pos);
+            if byte & 0x80 == 0 {
+                length_size = pos + 1;
+                break;
+            }
+        }
+        if length_size == 0 {
+            if buf.len() >= VarInt::MAX_LEN {
+                return Err(VarIntErr::TooLongError.into());
+            }
+            return Ok(None);
+        }
+        if buf.len() < length_size + length {
+            return Ok(None);
+        }
+
+        buf.advance(length_size);
+        let mut data = buf.split_to(length).freeze();
         let id = VarInt::read(&mut data)?;
 
-        Ok(Packet { length, id, data })
+        Ok(Some(Packet { length, id, data }))
     }
 
     pub async fn send(self, stream: &mut WriteHalf<'_>) -> anyhow::Result<()> {
@@ -1,11 +1,19 @@
 use crate::protocol::packet::Packet;
 use crate::protocol::types::{MCString, ReadBuffer, VarInt};
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
 use tokio::net::tcp::ReadHalf;
 use uuid::Uuid;
 
+/// The client closed the connection between packets, the normal way for a connection to end.
+#[derive(Error, Debug)]
+#[error("Connection closed by client")]
+pub struct ConnectionClosed;
+
 pub struct ProtocolReader<'a> {
     stream: ReadHalf<'a>,
+    buf: BytesMut,
     packet_id: VarInt,
     packet_length: usize,
     data: Bytes,
@@ -15,6 +23,7 @@ impl<'a> ProtocolReader<'a> {
     pub fn from_stream(stream: ReadHalf<'a>) -> anyhow::Result<ProtocolReader<'a>> {
         let reader = ProtocolReader {
             stream,
+            buf: BytesMut::default(),
             packet_id: VarInt::default(),
             packet_length: 0,
             data: Bytes::default(),
@@ -22,54 +31,63 @@ impl<'a> ProtocolReader<'a> {
         Ok(reader)
     }
 
-    pub async fn packet_id(&mut self) -> anyhow::Result<VarInt> {
-        self.check_for_packet_end().await?;
-        Ok(self.packet_id)
-    }
-
-    pub async fn packet_length(&mut self) -> anyhow::Result<usize> {
+    /// ID and length of the next packet, read once before its body.
+    ///
+    /// Once the body is read, an empty body can't be told apart from a finished packet,
+    /// so asking again would load the packet after it.
+    pub async fn packet_header(&mut self) -> anyhow::Result<(VarInt, usize)> {
         self.check_for_packet_end().await?;
-        Ok(self.packet_length)
+        Ok((self.packet_id, self.packet_length))
     }
 
     pub async fn read_varint(&mut self) -> anyhow::Result<VarInt> {
         self.check_for_packet_end().await?;
-        Ok(VarInt::read(&mut self.data)?)
+        VarInt::read(&mut self.data)
     }
 
     pub async fn read_i8(&mut self) -> anyhow::Result<i8> {
         self.check_for_packet_end().await?;
-        Ok(i8::read(&mut self.data)?)
+        i8::read(&mut self.data)
     }
 
     pub async fn read_u8(&mut self) -> anyhow::Result<u8> {
         self.check_for_packet_end().await?;
-        Ok(u8::read(&mut self.data)?)
+        u8::read(&mut self.data)
     }
 
     pub async fn read_bool(&mut self) -> anyhow::Result<bool> {
         self.check_for_packet_end().await?;
-        Ok(bool::read(&mut self.data)?)
+        bool::read(&mut self.data)
     }
 
     pub async fn read_u16(&mut self) -> anyhow::Result<u16> {
         self.check_for_packet_end().await?;
-        Ok(u16::read(&mut self.data)?)
+        u16::read(&mut self.data)
     }
 
     pub async fn read_i64(&mut self) -> anyhow::Result<i64> {
         self.check_for_packet_end().await?;
-        Ok(i64::read(&mut self.data)?)
+        i64::read(&mut self.data)
     }
 
     pub async fn read_string(&mut self) -> anyhow::Result<MCString> {
         self.check_for_packet_end().await?;
-        Ok(MCString::read(&mut self.data)?)
+        MCString::read(&mut self.data)
     }
 
     pub async fn read_uuid(&mut self) -> anyhow::Result<Uuid> {
         self.check_for_packet_end().await?;
-        Ok(Uuid::read(&mut self.data)?)
+        Uuid::read(&mut self.data)
+    }
+
+    pub async fn read<T: ReadBuffer>(&mut self) -> anyhow::Result<T> {
+        self.check_for_packet_end().await?;
+        T::read(&mut self.data)
+    }
+
+    /// Takes the unread rest of the current packet, e.g. a plugin message payload.
+    pub async fn read_remaining(&mut self) -> anyhow::Result<Bytes> {
+        Ok(std::mem::take(&mut self.data))
     }
 
     async fn check_for_packet_end(&mut self) -> anyhow::Result<()> {
@@ -79,14 +97,19 @@ impl<'a> ProtocolReader<'a> {
         Ok(())
     }
 
+    /// Reads until a whole packet is buffered, so a cancelled read never loses bytes.
     async fn load_next_packet(&mut self) -> anyhow::Result<()> {
-        self.stream.readable().await?;
-
-        let packet = Packet::read(&mut self.stream).await?;
-        self.packet_id = packet.id;
-        self.packet_length = packet.length;
-        self.data = packet.data;
-
-        Ok(())
+        loop {
+            if let Some(packet) = Packet::decode(&mut self.buf)? {
+                self.packet_id = packet.id;
+                self.packet_length = packet.length;
+                self.data = packet.data;
+                return Ok(());
+            }
+
+            if self.stream.read_buf(&mut self.buf).await? == 0 {
+                return Err(ConnectionClosed.into());
+            }
+        }
     }
 }
@@ -1,8 +1,14 @@
+mod client_settings;
 pub mod enums;
+mod location;
 mod nbt;
 mod position;
 mod primitives;
+mod profile;
+mod skin_parts;
 mod string;
+mod tag;
+mod text;
 mod uuid;
 mod varint;
 mod vector;
@@ -17,8 +23,23 @@ pub trait WriteBuffer {
     fn write(self, buf: &mut BytesMut) -> anyhow::Result<()>;
 }
 
-use bytes::{Bytes, BytesMut};
+/// Splits off the next `len` bytes, failing on truncated input instead of panicking.
+fn split_checked(buf: &mut Bytes, len: usize) -> anyhow::Result<Bytes> {
+    if buf.remaining() < len {
+        bail!("Expected {} more bytes, only {} left", len, buf.remaining());
+    }
+    Ok(buf.split_to(len))
+}
+
+use anyhow::bail;
+use bytes::{Buf, Bytes, BytesMut};
+pub use client_settings::ClientSettings;
+pub use location::Location;
 pub use nbt::NBTString;
 pub use position::Position;
+pub use profile::{GameProfile, ProfileProperty};
+pub use skin_parts::SkinParts;
 pub use string::{Identifier, MCString};
-pub use varint::VarInt;
+pub use tag::{RegistryTags, Tag};
+pub use text::TextComponent;
+pub use varint::{VarInt, VarIntErr};
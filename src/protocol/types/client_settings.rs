@@ -0,0 +1,83 @@
+use crate::protocol::types::enums::{ChatMode, Hand, ParticleStatus};
+use crate::protocol::types::{MCString, ReadBuffer, SkinParts, VarInt};
+use bytes::Bytes;
+
+/// Client settings sent in `Client Information` during Configuration and Play.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientSettings {
+    pub locale: MCString,
+    pub view_distance: i8,
+    pub chat_mode: ChatMode,
+    pub enable_chat_colors: bool,
+    pub displayed_skin_parts: SkinParts,
+    pub main_hand: Hand,
+    pub enable_text_filtering: bool,
+    pub allow_server_listings: bool,
+    pub particle_status: ParticleStatus,
+}
+
+impl ClientSettings {
+    /// View distance actually used for the client, capped by the server limit.
+    pub fn effective_view_distance(&self, server_view_distance: i32) -> i32 {
+        (self.view_distance as i32).clamp(2, server_view_distance.max(2))
+    }
+}
+
+impl ReadBuffer for ClientSettings {
+    fn read(buf: &mut Bytes) -> anyhow::Result<ClientSettings> {
+        Ok(ClientSettings {
+            locale: MCString::read(buf)?,
+            view_distance: i8::read(buf)?,
+            chat_mode: VarInt::read(buf)?.try_into()?,
+            enable_chat_colors: bool::read(buf)?,
+            displayed_skin_parts: SkinParts::read(buf)?,
+            main_hand: VarInt::read(buf)?.try_into()?,
+            enable_text_filtering: bool::read(buf)?,
+            allow_server_listings: bool::read(buf)?,
+            particle_status: VarInt::read(buf)?.try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::WriteBuffer;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_read_correctness() {
+        let mut buf = BytesMut::new();
+        MCString::from("en_us").write(&mut buf).unwrap();
+        12i8.write(&mut buf).unwrap();
+        VarInt::new(1).write(&mut buf).unwrap();
+        true.write(&mut buf).unwrap();
+        0x45u8.write(&mut buf).unwrap();
+        VarInt::new(0).write(&mut buf).unwrap();
+        false.write(&mut buf).unwrap();
+        true.write(&mut buf).unwrap();
+        VarInt::new(2).write(&mut buf).unwrap();
+
+        let settings = ClientSettings::read(&mut buf.freeze()).unwrap();
+
+        assert_eq!(settings.locale, "en_us");
+        assert_eq!(settings.chat_mode, ChatMode::CommandsOnly);
+        assert_eq!(
+            settings.displayed_skin_parts,
+            SkinParts::CAPE | SkinParts::LEFT_SLEEVE | SkinParts::HAT
+        );
+        assert_eq!(settings.main_hand, Hand::Left);
+        assert_eq!(settings.particle_status, ParticleStatus::Minimal);
+        assert_eq!(settings.effective_view_distance(8), 8);
+    }
+
+    #[test]
+    fn test_unknown_chat_mode() {
+        let mut buf = BytesMut::new();
+        MCString::from("en_us").write(&mut buf).unwrap();
+        12i8.write(&mut buf).unwrap();
+        VarInt::new(3).write(&mut buf).unwrap();
+
+        assert!(ClientSettings::read(&mut buf.freeze()).is_err());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::protocol::types::VarInt;
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum ClientState {
@@ -8,17 +10,49 @@ pub enum ClientState {
     Play,
 }
 
-impl From<VarInt> for ClientState {
-    fn from(value: VarInt) -> Self {
-        match value.into() {
-            1 => ClientState::Status,
-            2 => ClientState::Login,
-            3 => ClientState::Play,
-            _ => panic!("Unknown handshake client state: {}", value),
+impl ClientState {
+    /// Lowercase name used as a metric label.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClientState::Status => "status",
+            ClientState::Login => "login",
+            ClientState::Configuration => "configuration",
+            ClientState::Play => "play",
+        }
+    }
+}
+
+/// Next state requested by the client in the handshake.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Intent {
+    Status,
+    Login,
+    /// Login of a player sent to this server with `Transfer`.
+    Transfer,
+}
+
+impl Intent {
+    pub fn state(&self) -> ClientState {
+        match self {
+            Intent::Status => ClientState::Status,
+            Intent::Login | Intent::Transfer => ClientState::Login,
         }
     }
 }
 
+impl TryFrom<VarInt> for Intent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
+            1 => Intent::Status,
+            2 => Intent::Login,
+            3 => Intent::Transfer,
+            _ => bail!("Unknown handshake intent: {}", value),
+        })
+    }
+}
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum ChatMode {
     Enabled = 0,
@@ -26,14 +60,16 @@ pub enum ChatMode {
     Hidden = 2,
 }
 
-impl From<VarInt> for ChatMode {
-    fn from(value: VarInt) -> Self {
-        match value.into() {
+impl TryFrom<VarInt> for ChatMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
             0 => ChatMode::Enabled,
             1 => ChatMode::CommandsOnly,
             2 => ChatMode::Hidden,
-            _ => panic!("Unknown chat mode: {}", value),
-        }
+            _ => bail!("Unknown chat mode: {}", value),
+        })
     }
 }
 
@@ -43,13 +79,89 @@ pub enum Hand {
     Right = 1,
 }
 
-impl From<VarInt> for Hand {
-    fn from(value: VarInt) -> Self {
-        match value.into() {
+impl TryFrom<VarInt> for Hand {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
             0 => Hand::Left,
             1 => Hand::Right,
-            _ => panic!("Unknown hand: {}", value),
-        }
+            _ => bail!("Unknown hand: {}", value),
+        })
+    }
+}
+
+/// Hand used to interact with a block or item.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum InteractionHand {
+    MainHand,
+    OffHand,
+}
+
+impl TryFrom<VarInt> for InteractionHand {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
+            0 => InteractionHand::MainHand,
+            1 => InteractionHand::OffHand,
+            _ => bail!("Unknown interaction hand: {}", value),
+        })
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum BlockFace {
+    Bottom,
+    Top,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl TryFrom<VarInt> for BlockFace {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
+            0 => BlockFace::Bottom,
+            1 => BlockFace::Top,
+            2 => BlockFace::North,
+            3 => BlockFace::South,
+            4 => BlockFace::West,
+            5 => BlockFace::East,
+            _ => bail!("Unknown block face: {}", value),
+        })
+    }
+}
+
+/// Status of the `Player Action` packet.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum PlayerActionStatus {
+    StartedDigging,
+    CancelledDigging,
+    FinishedDigging,
+    DropItemStack,
+    DropItem,
+    ReleaseUseItem,
+    SwapItemInHand,
+}
+
+impl TryFrom<VarInt> for PlayerActionStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
+            0 => PlayerActionStatus::StartedDigging,
+            1 => PlayerActionStatus::CancelledDigging,
+            2 => PlayerActionStatus::FinishedDigging,
+            3 => PlayerActionStatus::DropItemStack,
+            4 => PlayerActionStatus::DropItem,
+            5 => PlayerActionStatus::ReleaseUseItem,
+            6 => PlayerActionStatus::SwapItemInHand,
+            _ => bail!("Unknown player action status: {}", value),
+        })
     }
 }
 
@@ -60,24 +172,27 @@ pub enum ParticleStatus {
     Minimal,
 }
 
-impl From<VarInt> for ParticleStatus {
-    fn from(value: VarInt) -> Self {
-        match value.into() {
+impl TryFrom<VarInt> for ParticleStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
             0 => ParticleStatus::All,
             1 => ParticleStatus::Decreased,
             2 => ParticleStatus::Minimal,
-            _ => panic!("Unknown particle status: {}", value),
-        }
+            _ => bail!("Unknown particle status: {}", value),
+        })
     }
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum GameMode {
-    Undefined,
-    Survival,
-    Creative,
-    Adventure,
-    Spectator,
+    Undefined = -1,
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
 }
 
 impl From<VarInt> for GameMode {
@@ -93,8 +208,81 @@ impl From<VarInt> for GameMode {
     }
 }
 
-impl Into<VarInt> for GameMode {
-    fn into(self) -> VarInt {
-        VarInt::new(self as i32)
+impl From<GameMode> for VarInt {
+    fn from(value: GameMode) -> Self {
+        VarInt::new(value as i32)
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ResourcePackStatus {
+    SuccessfullyDownloaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+    Downloaded,
+    InvalidUrl,
+    FailedToReload,
+    Discarded,
+}
+
+impl ResourcePackStatus {
+    /// Whether the client is done with the pack, successfully or not.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, ResourcePackStatus::Accepted | ResourcePackStatus::Downloaded)
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.is_final() && *self != ResourcePackStatus::SuccessfullyDownloaded
+    }
+}
+
+impl TryFrom<VarInt> for ResourcePackStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: VarInt) -> anyhow::Result<Self> {
+        Ok(match value.into() {
+            0 => ResourcePackStatus::SuccessfullyDownloaded,
+            1 => ResourcePackStatus::Declined,
+            2 => ResourcePackStatus::FailedDownload,
+            3 => ResourcePackStatus::Accepted,
+            4 => ResourcePackStatus::Downloaded,
+            5 => ResourcePackStatus::InvalidUrl,
+            6 => ResourcePackStatus::FailedToReload,
+            7 => ResourcePackStatus::Discarded,
+            _ => bail!("Unknown resource pack status: {}", value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_resource_pack_status() {
+        assert_eq!(
+            ResourcePackStatus::try_from(VarInt::new(7)).unwrap(),
+            ResourcePackStatus::Discarded
+        );
+        assert!(ResourcePackStatus::try_from(VarInt::new(8)).is_err());
+        assert!(ResourcePackStatus::try_from(VarInt::new(-1)).is_err());
+    }
+
+    #[test]
+    fn test_handshake_intent() {
+        assert_eq!(Intent::try_from(VarInt::new(1)).unwrap().state(), ClientState::Status);
+        assert_eq!(Intent::try_from(VarInt::new(3)).unwrap(), Intent::Transfer);
+        assert_eq!(Intent::Transfer.state(), ClientState::Login);
+        assert!(Intent::try_from(VarInt::new(0)).is_err());
+        assert!(Intent::try_from(VarInt::new(4)).is_err());
+    }
+
+    #[test]
+    fn test_unknown_player_input() {
+        assert_eq!(BlockFace::try_from(VarInt::new(5)).unwrap(), BlockFace::East);
+        assert!(BlockFace::try_from(VarInt::new(6)).is_err());
+        assert!(InteractionHand::try_from(VarInt::new(2)).is_err());
+        assert!(PlayerActionStatus::try_from(VarInt::new(-1)).is_err());
     }
 }
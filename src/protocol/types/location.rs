@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// Position and rotation of an entity in the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Location {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rotation(mut self, yaw: f32, pitch: f32) -> Self {
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn distance_squared(&self, other: &Location) -> f64 {
+        (self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)
+    }
+}
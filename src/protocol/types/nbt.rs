@@ -1,4 +1,5 @@
 use crate::protocol::types::WriteBuffer;
+use anyhow::Context;
 use bytes::{BufMut, BytesMut};
 use std::borrow::Cow;
 use std::fmt::Display;
@@ -7,20 +8,26 @@ use std::fmt::Display;
 pub struct NBTString(Cow<'static, str>);
 
 impl NBTString {
-    const ID: u8 = 8;
+    pub(crate) const ID: u8 = 8;
 
     pub fn new(value: Cow<'static, str>) -> Self {
         Self(value)
     }
+
+    /// Writes a string payload (also used for tag names): its length and Java modified UTF-8 bytes.
+    pub(crate) fn write_payload(value: &str, buf: &mut BytesMut) -> anyhow::Result<()> {
+        let value = cesu8::to_java_cesu8(value);
+        let length = u16::try_from(value.len()).context("NBT string is too long")?;
+        u16::write(length, buf)?;
+        buf.put_slice(&value);
+        Ok(())
+    }
 }
 
 impl WriteBuffer for NBTString {
     fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
-        let value = cesu8::to_java_cesu8(self.0.as_ref());
-        buf.put_slice(&[Self::ID]);
-        u16::write(value.len() as u16, buf)?;
-        buf.put_slice(&value);
-        Ok(())
+        buf.put_u8(Self::ID);
+        Self::write_payload(self.0.as_ref(), buf)
     }
 }
 
@@ -36,9 +43,9 @@ impl From<String> for NBTString {
     }
 }
 
-impl Into<String> for NBTString {
-    fn into(self) -> String {
-        self.0.into()
+impl From<NBTString> for String {
+    fn from(value: NBTString) -> Self {
+        value.0.into()
     }
 }
 
@@ -1,13 +1,46 @@
-use crate::protocol::types::{ReadBuffer, WriteBuffer};
+use crate::protocol::types::enums::BlockFace;
+use crate::protocol::types::{split_checked, ReadBuffer, WriteBuffer};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use std::fmt::Debug;
 
 const I26_MASK_U64: u64 = (1u64 << 26) - 1;
 const I12_MASK_U64: u64 = (1u64 << 12) - 1;
 
+/// Block position packed into a long: x (26 bits), z (26 bits) and y (12 bits).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Position(i32, i32, i16);
 
+impl Position {
+    pub fn new(x: i32, y: i16, z: i32) -> Self {
+        Position(x, z, y)
+    }
+
+    pub fn x(&self) -> i32 {
+        self.0
+    }
+
+    pub fn y(&self) -> i16 {
+        self.2
+    }
+
+    pub fn z(&self) -> i32 {
+        self.1
+    }
+
+    /// Neighbouring block on the given face.
+    pub fn relative(&self, face: BlockFace) -> Position {
+        let (x, y, z) = (self.x(), self.y(), self.z());
+        match face {
+            BlockFace::Bottom => Position::new(x, y - 1, z),
+            BlockFace::Top => Position::new(x, y + 1, z),
+            BlockFace::North => Position::new(x, y, z - 1),
+            BlockFace::South => Position::new(x, y, z + 1),
+            BlockFace::West => Position::new(x - 1, y, z),
+            BlockFace::East => Position::new(x + 1, y, z),
+        }
+    }
+}
+
 fn sign_extend(v: u64, width: u32) -> i64 {
     debug_assert!(width > 0 && width <= 63);
     let sign_bit = 1u64 << (width - 1);
@@ -22,24 +55,24 @@ fn sign_extend(v: u64, width: u32) -> i64 {
 
 impl ReadBuffer for Position {
     fn read(buf: &mut Bytes) -> anyhow::Result<Position> {
-        let word = buf.split_to(size_of::<u64>()).get_u64();
+        let word = split_checked(buf, size_of::<u64>())?.get_u64();
 
-        let x = sign_extend(word & I26_MASK_U64, 26) as i32;
-        let y = sign_extend((word >> 26) & I26_MASK_U64, 26) as i32;
-        let z = sign_extend((word >> 52) & I12_MASK_U64, 12) as i16;
+        let x = sign_extend(word >> 38, 26) as i32;
+        let z = sign_extend((word >> 12) & I26_MASK_U64, 26) as i32;
+        let y = sign_extend(word & I12_MASK_U64, 12) as i16;
 
-        Ok(Position(x, y, z))
+        Ok(Position(x, z, y))
     }
 }
 
 impl WriteBuffer for Position {
     fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
-        let Position(x, y, z) = self;
+        let Position(x, z, y) = self;
         let x_bits = (x as u32 as u64) & I26_MASK_U64;
-        let y_bits = (y as u32 as u64) & I26_MASK_U64;
-        let z_bits = (z as u16 as u64) & I12_MASK_U64;
+        let z_bits = (z as u32 as u64) & I26_MASK_U64;
+        let y_bits = (y as u16 as u64) & I12_MASK_U64;
 
-        buf.put_u64(x_bits | (y_bits << 26) | (z_bits << 52));
+        buf.put_u64((x_bits << 38) | (z_bits << 12) | y_bits);
         Ok(())
     }
 }
@@ -51,10 +84,24 @@ mod tests {
     fn test_read_write_correctness() {
         let mut buf = BytesMut::new();
         let expected = Position(-33554432, 33554431, -2048);
-        
+
         expected.clone().write(&mut buf).unwrap();
         let actual = Position::read(&mut buf.freeze()).unwrap();
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test_wire_layout() {
+        let mut buf = BytesMut::new();
+
+        Position::new(18357644, 831, -20882616).write(&mut buf).unwrap();
+
+        assert_eq!(&buf[..], &0x4607632C15B4833Fu64.to_be_bytes());
+    }
+
+    #[test]
+    fn test_truncated() {
+        assert!(Position::read(&mut Bytes::from_static(&[0x46, 0x07, 0x63])).is_err());
+    }
 }
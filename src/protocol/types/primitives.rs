@@ -1,11 +1,11 @@
-use crate::protocol::types::{ReadBuffer, WriteBuffer};
+use crate::protocol::types::{split_checked, ReadBuffer, WriteBuffer};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 macro_rules! impl_buffer {
     ($t:ty, $get:ident, $put:ident) => {
         impl ReadBuffer for $t {
             fn read(buf: &mut Bytes) -> anyhow::Result<$t> {
-                let mut value = buf.split_to(size_of::<$t>());
+                let mut value = split_checked(buf, size_of::<$t>())?;
                 Ok(value.$get())
             }
         }
@@ -27,6 +27,8 @@ impl_buffer!(i32, get_i32, put_i32);
 impl_buffer!(u32, get_u32, put_u32);
 impl_buffer!(i64, get_i64, put_i64);
 impl_buffer!(u64, get_u64, put_u64);
+impl_buffer!(f32, get_f32, put_f32);
+impl_buffer!(f64, get_f64, put_f64);
 
 impl ReadBuffer for bool {
     fn read(buf: &mut Bytes) -> anyhow::Result<bool> {
@@ -40,3 +42,26 @@ impl WriteBuffer for bool {
         Ok(())
     }
 }
+
+impl WriteBuffer for Bytes {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        buf.put_slice(&self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::{MCString, VarInt};
+
+    #[test]
+    fn test_truncated() {
+        assert!(i64::read(&mut Bytes::from_static(&[0, 0, 0, 1])).is_err());
+
+        let mut buf = BytesMut::new();
+        VarInt::new(5).write(&mut buf).unwrap();
+        buf.put_slice(b"ab");
+        assert!(MCString::read(&mut buf.freeze()).is_err());
+    }
+}
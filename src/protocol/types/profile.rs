@@ -0,0 +1,72 @@
+use crate::protocol::types::{MCString, ReadBuffer, WriteBuffer};
+use bytes::{Bytes, BytesMut};
+use uuid::Uuid;
+
+/// Identity of a logged in player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameProfile {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Signed profile property, e.g. the skin `textures`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl GameProfile {
+    pub fn new(uuid: Uuid, name: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+            properties: Vec::new(),
+        }
+    }
+}
+
+impl ProfileProperty {
+    pub fn new(name: impl Into<String>, value: impl Into<String>, signature: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            signature,
+        }
+    }
+}
+
+impl WriteBuffer for GameProfile {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        self.uuid.write(buf)?;
+        MCString::from(self.name).write(buf)?;
+        self.properties.write(buf)
+    }
+}
+
+impl ReadBuffer for ProfileProperty {
+    fn read(buf: &mut Bytes) -> anyhow::Result<ProfileProperty> {
+        let name = MCString::read(buf)?.into();
+        let value = MCString::read(buf)?.into();
+        let signature = match bool::read(buf)? {
+            true => Some(MCString::read(buf)?.into()),
+            false => None,
+        };
+
+        Ok(ProfileProperty { name, value, signature })
+    }
+}
+
+impl WriteBuffer for ProfileProperty {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        MCString::from(self.name).write(buf)?;
+        MCString::from(self.value).write(buf)?;
+        self.signature.is_some().write(buf)?;
+        if let Some(signature) = self.signature {
+            MCString::from(signature).write(buf)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::protocol::types::{ReadBuffer, WriteBuffer};
+use bitflags::bitflags;
+use bytes::{Bytes, BytesMut};
+
+bitflags! {
+    /// Displayed skin parts bit mask from `Client Information`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct SkinParts: u8 {
+        const CAPE = 0x01;
+        const JACKET = 0x02;
+        const LEFT_SLEEVE = 0x04;
+        const RIGHT_SLEEVE = 0x08;
+        const LEFT_PANTS_LEG = 0x10;
+        const RIGHT_PANTS_LEG = 0x20;
+        const HAT = 0x40;
+    }
+}
+
+impl ReadBuffer for SkinParts {
+    fn read(buf: &mut Bytes) -> anyhow::Result<SkinParts> {
+        Ok(SkinParts::from_bits_retain(u8::read(buf)?))
+    }
+}
+
+impl WriteBuffer for SkinParts {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        self.bits().write(buf)
+    }
+}
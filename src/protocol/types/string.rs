@@ -1,4 +1,4 @@
-use crate::protocol::types::{ReadBuffer, VarInt, WriteBuffer};
+use crate::protocol::types::{split_checked, ReadBuffer, VarInt, WriteBuffer};
 use bytes::{Bytes, BytesMut};
 use std::borrow::Cow;
 use std::fmt::{Display, Formatter, Write};
@@ -14,12 +14,16 @@ impl MCString {
     pub fn new(value: Cow<'static, str>) -> Self {
         Self(value)
     }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
 }
 
 impl ReadBuffer for MCString {
     fn read(buf: &mut Bytes) -> anyhow::Result<MCString> {
         let length = VarInt::read(buf)?;
-        let value = String::from_utf8_lossy(&buf.split_to(length.into())).into_owned();
+        let value = String::from_utf8_lossy(&split_checked(buf, length.into())?).into_owned();
         Ok(value.into())
     }
 }
@@ -49,9 +53,9 @@ impl From<String> for MCString {
     }
 }
 
-impl Into<String> for MCString {
-    fn into(self) -> String {
-        self.0.into()
+impl From<MCString> for String {
+    fn from(value: MCString) -> Self {
+        value.0.into()
     }
 }
 
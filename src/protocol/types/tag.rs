@@ -0,0 +1,28 @@
+use crate::protocol::types::{Identifier, VarInt, WriteBuffer};
+use bytes::BytesMut;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: Identifier,
+    pub entries: Vec<VarInt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryTags {
+    pub registry: Identifier,
+    pub tags: Vec<Tag>,
+}
+
+impl WriteBuffer for Tag {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        self.name.write(buf)?;
+        self.entries.write(buf)
+    }
+}
+
+impl WriteBuffer for RegistryTags {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        self.registry.write(buf)?;
+        self.tags.write(buf)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::protocol::types::{NBTString, WriteBuffer};
+use bytes::{BufMut, BytesMut};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_STRING: u8 = NBTString::ID;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+
+/// Chat text component, encoded as JSON in Login and as network NBT in Configuration and Play.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextComponent {
+    pub text: String,
+    pub color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub extra: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn append(mut self, component: impl Into<TextComponent>) -> Self {
+        self.extra.push(component.into());
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut json = Map::new();
+        json.insert("text".into(), self.text.clone().into());
+        if let Some(color) = &self.color {
+            json.insert("color".into(), color.clone().into());
+        }
+        if let Some(bold) = self.bold {
+            json.insert("bold".into(), bold.into());
+        }
+        if let Some(italic) = self.italic {
+            json.insert("italic".into(), italic.into());
+        }
+        if !self.extra.is_empty() {
+            json.insert("extra".into(), self.extra.iter().map(Self::to_json).collect());
+        }
+        Value::Object(json)
+    }
+
+    /// Text without formatting, e.g. for logs.
+    pub fn to_plain(&self) -> String {
+        let mut plain = self.text.clone();
+        for extra in &self.extra {
+            plain.push_str(&extra.to_plain());
+        }
+        plain
+    }
+
+    fn write_compound_body(&self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        write_string_entry(buf, "text", &self.text)?;
+        if let Some(color) = &self.color {
+            write_string_entry(buf, "color", color)?;
+        }
+        if let Some(bold) = self.bold {
+            write_byte_entry(buf, "bold", bold as i8)?;
+        }
+        if let Some(italic) = self.italic {
+            write_byte_entry(buf, "italic", italic as i8)?;
+        }
+        if !self.extra.is_empty() {
+            buf.put_u8(TAG_LIST);
+            NBTString::write_payload("extra", buf)?;
+            buf.put_u8(TAG_COMPOUND);
+            buf.put_i32(self.extra.len() as i32);
+            for extra in &self.extra {
+                extra.write_compound_body(buf)?;
+            }
+        }
+        buf.put_u8(TAG_END);
+        Ok(())
+    }
+}
+
+fn write_string_entry(buf: &mut BytesMut, name: &str, value: &str) -> anyhow::Result<()> {
+    buf.put_u8(TAG_STRING);
+    NBTString::write_payload(name, buf)?;
+    NBTString::write_payload(value, buf)
+}
+
+fn write_byte_entry(buf: &mut BytesMut, name: &str, value: i8) -> anyhow::Result<()> {
+    buf.put_u8(TAG_BYTE);
+    NBTString::write_payload(name, buf)?;
+    buf.put_i8(value);
+    Ok(())
+}
+
+impl WriteBuffer for TextComponent {
+    fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
+        // Network NBT has a nameless root tag
+        buf.put_u8(TAG_COMPOUND);
+        self.write_compound_body(buf)
+    }
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Display for TextComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_plain())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_nbt_correctness() {
+        let mut buf = BytesMut::new();
+        TextComponent::new("a").bold(true).write(&mut buf).unwrap();
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            TAG_COMPOUND,
+            TAG_STRING, 0, 4, b't', b'e', b'x', b't', 0, 1, b'a',
+            TAG_BYTE, 0, 4, b'b', b'o', b'l', b'd', 1,
+            TAG_END,
+        ];
+        assert_eq!(&buf[..], expected);
+    }
+
+    #[test]
+    fn test_json_and_plain_text() {
+        let text = TextComponent::new("Bye, ").color("red").append("Steve");
+
+        assert_eq!(
+            text.to_json().to_string(),
+            r#"{"color":"red","extra":[{"text":"Steve"}],"text":"Bye, "}"#
+        );
+        assert_eq!(text.to_plain(), "Bye, Steve");
+    }
+
+    #[test]
+    fn test_too_long_text() {
+        let text = TextComponent::new("a".repeat(u16::MAX as usize + 1));
+
+        assert!(text.write(&mut BytesMut::new()).is_err());
+    }
+}
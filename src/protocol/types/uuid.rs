@@ -1,10 +1,10 @@
-use crate::protocol::types::{ReadBuffer, WriteBuffer};
+use crate::protocol::types::{split_checked, ReadBuffer, WriteBuffer};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use uuid::Uuid;
 
 impl ReadBuffer for Uuid {
     fn read(buf: &mut Bytes) -> anyhow::Result<Uuid> {
-        let mut value = buf.split_to(size_of::<u128>());
+        let mut value = split_checked(buf, size_of::<u128>())?;
         Ok(Uuid::from_u128(value.get_u128()))
     }
 }
@@ -9,9 +9,11 @@ use tokio::net::tcp::ReadHalf;
 pub enum VarIntErr {
     #[error("VarInt more than 5 bytes")]
     TooLongError,
+    #[error("VarInt ended before its last byte")]
+    TruncatedError,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VarInt(i32);
 
 impl VarInt {
@@ -57,7 +59,9 @@ impl ReadBuffer for VarInt {
         let mut result = 0;
 
         for pos in 0..VarInt::MAX_LEN {
-            let next_byte = buf[pos];
+            let Some(&next_byte) = buf.get(pos) else {
+                return Err(VarIntErr::TruncatedError.into());
+            };
 
             let value = (next_byte & 0x7F) as i32;
             result |= value << (7 * pos);
@@ -74,7 +78,8 @@ impl ReadBuffer for VarInt {
 
 impl WriteBuffer for VarInt {
     fn write(self, buf: &mut BytesMut) -> anyhow::Result<()> {
-        let mut value: i32 = self.into();
+        // Shifted unsigned, so negative values end after five bytes instead of sign-extending forever
+        let mut value = i32::from(self) as u32;
         loop {
             let temp = (value & 0x7F) as u8;
             value >>= 7;
@@ -89,21 +94,15 @@ impl WriteBuffer for VarInt {
     }
 }
 
-impl Default for VarInt {
-    fn default() -> Self {
-        Self(0)
+impl From<VarInt> for usize {
+    fn from(value: VarInt) -> Self {
+        value.0 as usize
     }
 }
 
-impl Into<usize> for VarInt {
-    fn into(self) -> usize {
-        self.0 as usize
-    }
-}
-
-impl Into<i32> for VarInt {
-    fn into(self) -> i32 {
-        self.0
+impl From<VarInt> for i32 {
+    fn from(value: VarInt) -> Self {
+        value.0
     }
 }
 
@@ -130,3 +129,38 @@ impl UpperHex for VarInt {
         write!(f, "{:X}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::enums::GameMode;
+
+    #[test]
+    fn test_read_write_correctness() {
+        for value in [0, 1, 127, 128, 25565, i32::MAX, -1, i32::MIN] {
+            let mut buf = BytesMut::new();
+            VarInt::new(value).write(&mut buf).unwrap();
+            assert_eq!(buf.len(), VarInt::sizeof(value));
+
+            let actual = VarInt::read(&mut buf.freeze()).unwrap();
+            assert_eq!(i32::from(actual), value);
+        }
+    }
+
+    #[test]
+    fn test_undefined_game_mode() {
+        let mut buf = BytesMut::new();
+        VarInt::from(GameMode::Undefined).write(&mut buf).unwrap();
+
+        assert_eq!(&buf[..], &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(GameMode::from(VarInt::read(&mut buf.freeze()).unwrap()), GameMode::Undefined);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let result = VarInt::read(&mut Bytes::from_static(&[0xFF, 0xFF]));
+
+        assert!(matches!(result.unwrap_err().downcast(), Ok(VarIntErr::TruncatedError)));
+        assert!(VarInt::read(&mut Bytes::new()).is_err());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::metrics::METRICS;
 use crate::protocol::packet::Packet;
+use crate::protocol::types::enums::ClientState;
 use crate::protocol::types::{VarInt, WriteBuffer};
 use bytes::BytesMut;
+use tokio::io::AsyncWriteExt;
 use tokio::net::tcp::WriteHalf;
 
 pub struct ProtocolWriter<'a> {
@@ -25,9 +28,14 @@ impl<'a> ProtocolWriter<'a> {
         value.write(&mut self.buf)
     }
 
-    pub async fn send_packet(&mut self, id: VarInt) -> anyhow::Result<()> {
+    pub async fn send_packet(&mut self, state: ClientState, id: VarInt) -> anyhow::Result<()> {
         let packet = Packet::new(id, std::mem::take(&mut self.buf).into());
+        METRICS.packet_sent(state.label(), id, packet.length);
 
         packet.send(&mut self.stream).await
     }
+
+    pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        Ok(self.stream.shutdown().await?)
+    }
 }
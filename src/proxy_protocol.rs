@@ -0,0 +1,121 @@
+use anyhow::{anyhow, bail};
+use ipnet::IpNet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LENGTH: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reads the PROXY protocol header a load balancer sends before the Minecraft handshake.
+///
+/// Peers outside of `trusted` are rejected, an empty list trusts nobody.
+/// Returns the original client address, or the peer address for `LOCAL`/`UNKNOWN` headers (health checks).
+pub async fn accept(stream: &mut TcpStream, peer: SocketAddr, trusted: &[IpNet]) -> anyhow::Result<SocketAddr> {
+    if !trusted.iter().any(|net| net.contains(&peer.ip())) {
+        bail!("PROXY protocol peer {} is not trusted", peer.ip());
+    }
+
+    let addr = tokio::time::timeout(HEADER_TIMEOUT, read_header(stream))
+        .await
+        .map_err(|_| anyhow!("Timed out waiting for the PROXY protocol header"))??;
+
+    Ok(addr.unwrap_or(peer))
+}
+
+async fn read_header(stream: &mut TcpStream) -> anyhow::Result<Option<SocketAddr>> {
+    // Both versions are longer than the v2 signature, so it is safe to read it up front
+    let mut header = vec![0u8; V2_SIGNATURE.len()];
+    stream.read_exact(&mut header).await?;
+
+    if header.starts_with(V1_PREFIX) {
+        while !header.ends_with(b"\r\n") {
+            if header.len() >= V1_MAX_LENGTH {
+                bail!("PROXY protocol v1 header is too long");
+            }
+            header.push(stream.read_u8().await?);
+        }
+        parse_v1(&header)
+    } else if header == V2_SIGNATURE {
+        let mut fixed = [0u8; 4];
+        stream.read_exact(&mut fixed).await?;
+        let mut addresses = vec![0u8; u16::from_be_bytes([fixed[2], fixed[3]]) as usize];
+        stream.read_exact(&mut addresses).await?;
+        parse_v2(fixed[0], fixed[1], &addresses)
+    } else {
+        bail!("Connection does not start with a PROXY protocol header")
+    }
+}
+
+/// Parses `PROXY TCP4|TCP6|UNKNOWN <src> <dst> <src port> <dst port>\r\n`.
+fn parse_v1(line: &[u8]) -> anyhow::Result<Option<SocketAddr>> {
+    let line = std::str::from_utf8(line)?.trim_end_matches("\r\n");
+    let parts: Vec<&str> = line.split(' ').collect();
+
+    match parts.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", src, _dst, src_port, _dst_port] => {
+            Ok(Some(SocketAddr::new(src.parse()?, src_port.parse()?)))
+        }
+        _ => bail!("Malformed PROXY protocol v1 header: {}", line),
+    }
+}
+
+fn parse_v2(version_command: u8, family: u8, addresses: &[u8]) -> anyhow::Result<Option<SocketAddr>> {
+    if version_command >> 4 != 2 {
+        bail!("Unsupported PROXY protocol version {}", version_command >> 4);
+    }
+
+    match (version_command & 0x0F, family >> 4) {
+        // LOCAL command, e.g. a health check from the balancer itself
+        (0x0, _) => Ok(None),
+        (0x1, 0x1) if addresses.len() >= 12 => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&addresses[0..4])?);
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        (0x1, 0x2) if addresses.len() >= 36 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&addresses[0..16])?);
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(ip), port)))
+        }
+        // UNSPEC or UNIX sockets carry no usable client address
+        (0x1, 0x0 | 0x3) => Ok(None),
+        (command, family) => bail!(
+            "Unsupported PROXY protocol v2 command {} for family {}",
+            command,
+            family
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v1() {
+        let addr = parse_v1(b"PROXY TCP4 192.0.2.1 10.0.0.1 56324 25565\r\n").unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+
+        let addr = parse_v1(b"PROXY TCP6 2001:db8::1 2001:db8::2 4000 25565\r\n").unwrap();
+        assert_eq!(addr, Some("[2001:db8::1]:4000".parse().unwrap()));
+
+        assert_eq!(parse_v1(b"PROXY UNKNOWN\r\n").unwrap(), None);
+        assert!(parse_v1(b"PROXY TCP4 nonsense\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_v2() {
+        let addresses = [192, 0, 2, 1, 10, 0, 0, 1, 0xDC, 0x04, 0x63, 0xDD];
+
+        let addr = parse_v2(0x21, 0x11, &addresses).unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+
+        assert_eq!(parse_v2(0x20, 0x00, &[]).unwrap(), None);
+        assert!(parse_v2(0x11, 0x11, &addresses).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::protocol::VERSION_NAME;
+use crate::server::Server;
+use anyhow::bail;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use tracing::{debug, info};
+
+const MAGIC: u16 = 0xFEFD;
+const TYPE_STAT: u8 = 0x00;
+const TYPE_HANDSHAKE: u8 = 0x09;
+/// Only the low 4 bits of every byte of the session id are significant.
+const SESSION_ID_MASK: i32 = 0x0F0F0F0F;
+/// Challenge tokens are valid for the current and the previous window.
+const CHALLENGE_WINDOW_SECS: u64 = 30;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+const MAX_PACKET_SIZE: usize = 1460;
+
+/// Request of the GameSpy4 UDP Query protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryRequest {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, challenge: i32 },
+    FullStat { session_id: i32, challenge: i32 },
+}
+
+impl QueryRequest {
+    pub fn parse(mut buf: &[u8]) -> anyhow::Result<Self> {
+        if buf.len() < 7 || buf.get_u16() != MAGIC {
+            bail!("Not a query packet");
+        }
+        let kind = buf.get_u8();
+        let session_id = buf.get_i32() & SESSION_ID_MASK;
+
+        match (kind, buf.len()) {
+            (TYPE_HANDSHAKE, _) => Ok(QueryRequest::Handshake { session_id }),
+            (TYPE_STAT, 4) => Ok(QueryRequest::BasicStat {
+                session_id,
+                challenge: buf.get_i32(),
+            }),
+            // The full stat request is padded with four bytes
+            (TYPE_STAT, 8) => Ok(QueryRequest::FullStat {
+                session_id,
+                challenge: buf.get_i32(),
+            }),
+            (kind, length) => bail!("Unknown query packet type {} of length {}", kind, length),
+        }
+    }
+}
+
+/// Live server data reported by the stat responses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryStats {
+    pub motd: String,
+    pub map: String,
+    pub plugins: String,
+    pub players: Vec<String>,
+    pub max_players: u32,
+    pub host_ip: String,
+    pub host_port: u16,
+}
+
+impl QueryStats {
+    pub fn from_server(server: &Server) -> Self {
+        Self {
+            motd: server.config.motd.text.clone(),
+            map: server.config.world.dimension.clone(),
+            plugins: plugin_list(server),
+            players: server
+                .players
+                .all()
+                .into_iter()
+                .map(|player| player.profile.name)
+                .collect(),
+            max_players: server.config.gameplay.max_players,
+            host_ip: server.config.network.host.clone(),
+            host_port: server.config.network.port,
+        }
+    }
+
+    pub fn write_basic(&self, buf: &mut BytesMut) {
+        put_str(buf, &self.motd);
+        put_str(buf, "SMP");
+        put_str(buf, &self.map);
+        put_str(buf, &self.players.len().to_string());
+        put_str(buf, &self.max_players.to_string());
+        buf.put_u16_le(self.host_port);
+        put_str(buf, &self.host_ip);
+    }
+
+    pub fn write_full(&self, buf: &mut BytesMut) {
+        buf.put_slice(b"splitnum\0\x80\0");
+        for (key, value) in [
+            ("hostname", self.motd.as_str()),
+            ("gametype", "SMP"),
+            ("game_id", "MINECRAFT"),
+            ("version", VERSION_NAME),
+            ("plugins", self.plugins.as_str()),
+            ("map", self.map.as_str()),
+            ("numplayers", &self.players.len().to_string()),
+            ("maxplayers", &self.max_players.to_string()),
+            ("hostport", &self.host_port.to_string()),
+            ("hostip", self.host_ip.as_str()),
+        ] {
+            put_str(buf, key);
+            put_str(buf, value);
+        }
+        buf.put_u8(0);
+
+        buf.put_slice(b"\x01player_\0\0");
+        for player in &self.players {
+            put_str(buf, player);
+        }
+        buf.put_u8(0);
+    }
+}
+
+/// Stateless challenge tokens derived from the client address and a per-process secret.
+pub struct ChallengeTokens {
+    secret: [u8; 32],
+}
+
+impl ChallengeTokens {
+    pub fn new() -> Self {
+        Self { secret: rand::random() }
+    }
+
+    pub fn issue(&self, addr: SocketAddr) -> i32 {
+        self.token(addr, current_window())
+    }
+
+    pub fn verify(&self, addr: SocketAddr, challenge: i32) -> bool {
+        let window = current_window();
+        challenge == self.token(addr, window) || challenge == self.token(addr, window.saturating_sub(1))
+    }
+
+    fn token(&self, addr: SocketAddr, window: u64) -> i32 {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(addr.to_string().as_bytes());
+        mac.update(&window.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        i32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+    }
+}
+
+impl Default for ChallengeTokens {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fixed window limit of packets per second for each source address.
+pub struct RateLimiter {
+    limit: u32,
+    windows: HashMap<IpAddr, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.windows.len() > 10_000 {
+            self.windows
+                .retain(|_, (start, _)| now.duration_since(*start) < RATE_LIMIT_WINDOW);
+        }
+
+        let (start, count) = self.windows.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_LIMIT_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.limit
+    }
+}
+
+/// Answers query requests until the server shuts down.
+pub async fn listen(server: Arc<Server>, socket: UdpSocket, rate_limit: u32) -> anyhow::Result<()> {
+    info!(addr = %socket.local_addr()?, "Query listening");
+
+    let tokens = ChallengeTokens::new();
+    let mut limiter = RateLimiter::new(rate_limit);
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+
+    loop {
+        let (length, addr) = tokio::select! {
+            received = socket.recv_from(&mut buf) => received?,
+            _ = server.wait_for_shutdown() => return Ok(()),
+        };
+        if !limiter.allow(addr.ip(), Instant::now()) {
+            continue;
+        }
+
+        match QueryRequest::parse(&buf[..length]) {
+            Ok(request) => {
+                if let Some(response) = respond(&server, &tokens, addr, request)
+                    && let Err(e) = socket.send_to(&response, addr).await
+                {
+                    debug!(peer = %addr, error = %e, "Failed to send query response");
+                }
+            }
+            Err(e) => debug!(peer = %addr, error = %e, "Invalid query packet"),
+        }
+    }
+}
+
+fn respond(server: &Server, tokens: &ChallengeTokens, addr: SocketAddr, request: QueryRequest) -> Option<Bytes> {
+    let mut buf = BytesMut::new();
+
+    match request {
+        QueryRequest::Handshake { session_id } => {
+            buf.put_u8(TYPE_HANDSHAKE);
+            buf.put_i32(session_id);
+            put_str(&mut buf, &tokens.issue(addr).to_string());
+        }
+        QueryRequest::BasicStat { session_id, challenge } if tokens.verify(addr, challenge) => {
+            buf.put_u8(TYPE_STAT);
+            buf.put_i32(session_id);
+            QueryStats::from_server(server).write_basic(&mut buf);
+        }
+        QueryRequest::FullStat { session_id, challenge } if tokens.verify(addr, challenge) => {
+            buf.put_u8(TYPE_STAT);
+            buf.put_i32(session_id);
+            QueryStats::from_server(server).write_full(&mut buf);
+        }
+        _ => return None,
+    }
+
+    Some(buf.freeze())
+}
+
+/// Vanilla format of the plugin list, `brand: plugin; plugin`.
+fn plugin_list(server: &Server) -> String {
+    match server.plugin_names().as_slice() {
+        [] => server.brand.clone(),
+        names => format!("{}: {}", server.brand, names.join("; ")),
+    }
+}
+
+fn put_str(buf: &mut BytesMut, value: &str) {
+    buf.put_slice(value.as_bytes());
+    buf.put_u8(0);
+}
+
+fn current_window() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / CHALLENGE_WINDOW_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        let handshake = [0xFE, 0xFD, 0x09, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            QueryRequest::parse(&handshake).unwrap(),
+            QueryRequest::Handshake { session_id: 1 }
+        );
+
+        let full = [
+            0xFE, 0xFD, 0x00, 0xF0, 0x00, 0x00, 0x01, 0x00, 0x91, 0x29, 0x5B, 0, 0, 0, 0,
+        ];
+        assert_eq!(
+            QueryRequest::parse(&full).unwrap(),
+            QueryRequest::FullStat {
+                session_id: 1,
+                challenge: 0x0091295B
+            }
+        );
+
+        assert!(QueryRequest::parse(&[0xFE, 0xFD, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_basic_stat() {
+        let stats = QueryStats {
+            motd: "A Minecraft Server".into(),
+            map: "world".into(),
+            plugins: String::new(),
+            players: vec!["Notch".into()],
+            max_players: 20,
+            host_ip: "127.0.0.1".into(),
+            host_port: 25565,
+        };
+
+        let mut buf = BytesMut::new();
+        stats.write_basic(&mut buf);
+
+        assert_eq!(
+            &buf[..],
+            b"A Minecraft Server\0SMP\0world\x001\x0020\0\xDD\x63127.0.0.1\0"
+        );
+    }
+
+    #[test]
+    fn test_challenge_tokens() {
+        let tokens = ChallengeTokens::new();
+        let addr = "192.0.2.1:4000".parse().unwrap();
+
+        let challenge = tokens.issue(addr);
+
+        assert!(tokens.verify(addr, challenge));
+        assert!(!tokens.verify("192.0.2.2:4000".parse().unwrap(), challenge));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(2);
+        let ip = "192.0.2.1".parse().unwrap();
+        let now = Instant::now();
+
+        assert!(limiter.allow(ip, now));
+        assert!(limiter.allow(ip, now));
+        assert!(!limiter.allow(ip, now));
+        assert!(limiter.allow(ip, now + RATE_LIMIT_WINDOW));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::command::{self, CommandSender};
+use crate::server::Server;
+use anyhow::bail;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+const TYPE_RESPONSE_VALUE: i32 = 0;
+const TYPE_EXEC_COMMAND: i32 = 2;
+const TYPE_AUTH_RESPONSE: i32 = 2;
+const TYPE_AUTH: i32 = 3;
+
+/// Request id of the auth response when the password is wrong.
+const AUTH_FAILED_ID: i32 = -1;
+/// Largest request body accepted, the same limit as the vanilla server.
+const MAX_REQUEST_BODY: usize = 1446;
+/// Responses are split into packets of at most this many bytes.
+const MAX_RESPONSE_BODY: usize = 4096;
+
+/// Packet of the Source RCON protocol, framed by a little-endian length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RconPacket {
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Self> {
+        let length = reader.read_i32_le().await?;
+        // id, kind and the two terminating NUL bytes
+        if length < 10 || length as usize > MAX_REQUEST_BODY + 10 {
+            bail!("Invalid RCON packet length {}", length);
+        }
+
+        let id = reader.read_i32_le().await?;
+        let kind = reader.read_i32_le().await?;
+        let mut body = vec![0u8; length as usize - 8];
+        reader.read_exact(&mut body).await?;
+        if !body.ends_with(&[0, 0]) {
+            bail!("RCON packet body is not NUL terminated");
+        }
+        body.truncate(body.len() - 2);
+
+        Ok(Self {
+            id,
+            kind,
+            body: String::from_utf8(body)?,
+        })
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let mut packet = Vec::with_capacity(self.body.len() + 14);
+        packet.extend_from_slice(&(self.body.len() as i32 + 10).to_le_bytes());
+        packet.extend_from_slice(&self.id.to_le_bytes());
+        packet.extend_from_slice(&self.kind.to_le_bytes());
+        packet.extend_from_slice(self.body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        writer.write_all(&packet).await?;
+        Ok(())
+    }
+}
+
+/// Accepts RCON clients until the server shuts down.
+pub async fn listen(server: Arc<Server>, listener: TcpListener, password: String) -> anyhow::Result<()> {
+    info!(addr = %listener.local_addr()?, "RCON listening");
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = server.wait_for_shutdown() => return Ok(()),
+        };
+        let server = server.clone();
+        let password = password.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, addr, &server, &password).await {
+                warn!(peer = %addr, error = %e, "RCON connection error");
+            }
+        });
+    }
+}
+
+async fn handle_client(mut stream: TcpStream, addr: SocketAddr, server: &Server, password: &str) -> anyhow::Result<()> {
+    let mut authenticated = false;
+    let sender = CommandSender::Rcon(addr);
+
+    loop {
+        let packet = match RconPacket::read(&mut stream).await {
+            Ok(packet) => packet,
+            Err(e) if is_eof(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        match packet.kind {
+            TYPE_AUTH if packet.body == password => {
+                authenticated = true;
+                info!(peer = %addr, "RCON client authenticated");
+                reply(&mut stream, packet.id, TYPE_AUTH_RESPONSE, "").await?;
+            }
+            TYPE_AUTH => {
+                warn!(peer = %addr, "RCON client sent a wrong password");
+                reply(&mut stream, AUTH_FAILED_ID, TYPE_AUTH_RESPONSE, "").await?;
+                return Ok(());
+            }
+            _ if !authenticated => bail!("Command sent before authentication"),
+            TYPE_EXEC_COMMAND => {
+                info!(peer = %addr, command = %packet.body, "RCON command");
+                let output = command::execute(server, &sender, &packet.body).await;
+                for chunk in split_body(&output) {
+                    reply(&mut stream, packet.id, TYPE_RESPONSE_VALUE, chunk).await?;
+                }
+            }
+            // Clients detect the end of a multi-packet response by sending an empty
+            // packet after their command and waiting for it to be mirrored back.
+            TYPE_RESPONSE_VALUE => reply(&mut stream, packet.id, TYPE_RESPONSE_VALUE, "").await?,
+            kind => bail!("Unknown RCON packet type {}", kind),
+        }
+    }
+}
+
+async fn reply(stream: &mut TcpStream, id: i32, kind: i32, body: &str) -> anyhow::Result<()> {
+    RconPacket {
+        id,
+        kind,
+        body: body.into(),
+    }
+    .write(stream)
+    .await
+}
+
+/// Splits the response on char boundaries, an empty response is still sent as one packet.
+fn split_body(body: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = body;
+    while rest.len() > MAX_RESPONSE_BODY {
+        let mut end = MAX_RESPONSE_BODY;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks.push(rest);
+    chunks
+}
+
+fn is_eof(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_packet_roundtrip() {
+        let packet = RconPacket {
+            id: 7,
+            kind: TYPE_EXEC_COMMAND,
+            body: "list".into(),
+        };
+
+        let mut buf = Vec::new();
+        packet.write(&mut buf).await.unwrap();
+
+        assert_eq!(&buf[..4], &14i32.to_le_bytes());
+        assert_eq!(RconPacket::read(&mut buf.as_slice()).await.unwrap(), packet);
+    }
+
+    #[test]
+    fn test_split_body() {
+        let body = "a".repeat(MAX_RESPONSE_BODY * 2 + 1);
+
+        let chunks = split_body(&body);
+
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            [4096, 4096, 1]
+        );
+        assert_eq!(split_body(""), [""]);
+    }
+}
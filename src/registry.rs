@@ -0,0 +1,3 @@
+mod tags;
+
+pub use tags::TagRegistry;
@@ -0,0 +1,330 @@
+use crate::protocol::types::{Identifier, RegistryTags, Tag, VarInt};
+use anyhow::{anyhow, bail, Context};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+const BUNDLED_TAGS: &str = include_str!("tags.json");
+
+/// Registries whose tags are sent to the client, with their directory under `data/<namespace>/tags`.
+const TAGGED_REGISTRIES: [(&str, &str); 4] = [
+    ("minecraft:block", "block"),
+    ("minecraft:item", "item"),
+    ("minecraft:fluid", "fluid"),
+    ("minecraft:entity_type", "entity_type"),
+];
+
+type Tags = BTreeMap<Identifier, BTreeMap<Identifier, Vec<VarInt>>>;
+
+/// Tags of the block/item/fluid/entity registries sent to the client in `Update Tags`.
+///
+/// Entries are numeric registry IDs of the protocol version the server speaks.
+#[derive(Debug, Default)]
+pub struct TagRegistry {
+    tags: RwLock<Tags>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the tags bundled with the server.
+    pub fn bundled() -> anyhow::Result<Self> {
+        Self::from_json(BUNDLED_TAGS)
+    }
+
+    /// Parses tags from `{"<registry>": {"<tag>": [<id>, ...]}}` JSON.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let parsed: BTreeMap<String, BTreeMap<String, Vec<i32>>> = serde_json::from_str(json)?;
+
+        let registry = Self::new();
+        for (registry_name, tags) in parsed {
+            let registry_name: Identifier = registry_name.into();
+            for (tag, entries) in tags {
+                registry.insert(registry_name.clone(), tag, entries);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Builds tags from the output of the vanilla data generator
+    /// (`java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports --server`).
+    ///
+    /// Entry names are mapped to protocol IDs through `reports/registries.json`, `#tag` references are
+    /// resolved and optional entries missing from the registry are skipped.
+    pub fn from_generated(dir: &Path) -> anyhow::Result<Self> {
+        let registries_path = dir.join("reports").join("registries.json");
+        let registries = fs::read_to_string(&registries_path)
+            .with_context(|| format!("Failed to read {}", registries_path.display()))?;
+        let registries: BTreeMap<String, GeneratedRegistry> = serde_json::from_str(&registries)?;
+
+        let registry = Self::new();
+        for (registry_name, tags_dir) in TAGGED_REGISTRIES {
+            let ids = registries
+                .get(registry_name)
+                .ok_or_else(|| anyhow!("Registry {registry_name} is missing from {}", registries_path.display()))?;
+            let files = generated_tag_files(&dir.join("data"), tags_dir)?;
+
+            let mut resolved = BTreeMap::new();
+            for tag in files.keys() {
+                resolve_tag(tag, &files, ids, &mut resolved, &mut BTreeSet::new())
+                    .with_context(|| format!("Failed to resolve {registry_name} tag #{tag}"))?;
+            }
+            for (tag, entries) in resolved {
+                registry.insert(registry_name, tag, entries);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Serializes the tags into the format read by [`TagRegistry::from_json`].
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        let tags = self.tags.read().unwrap();
+        let tags: BTreeMap<&str, BTreeMap<&str, Vec<i32>>> = tags
+            .iter()
+            .map(|(registry, tags)| {
+                let tags = tags
+                    .iter()
+                    .map(|(tag, entries)| (tag.as_str(), entries.iter().map(|&entry| entry.into()).collect()))
+                    .collect();
+                (registry.as_str(), tags)
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&tags)?)
+    }
+
+    /// Creates the tag or replaces its entries.
+    pub fn insert(
+        &self,
+        registry: impl Into<Identifier>,
+        tag: impl Into<Identifier>,
+        entries: impl IntoIterator<Item = i32>,
+    ) {
+        let entries = entries.into_iter().map(VarInt::new).collect();
+        let mut tags = self.tags.write().unwrap();
+        tags.entry(registry.into()).or_default().insert(tag.into(), entries);
+    }
+
+    /// Appends entries to the tag, creating it if it does not exist yet.
+    pub fn extend(
+        &self,
+        registry: impl Into<Identifier>,
+        tag: impl Into<Identifier>,
+        entries: impl IntoIterator<Item = i32>,
+    ) {
+        let mut tags = self.tags.write().unwrap();
+        let tag = tags.entry(registry.into()).or_default().entry(tag.into()).or_default();
+        for entry in entries.into_iter().map(VarInt::new) {
+            if !tag.contains(&entry) {
+                tag.push(entry);
+            }
+        }
+    }
+
+    pub fn remove(&self, registry: &Identifier, tag: &Identifier) -> bool {
+        let mut tags = self.tags.write().unwrap();
+        tags.get_mut(registry).is_some_and(|tags| tags.remove(tag).is_some())
+    }
+
+    pub fn get(&self, registry: &Identifier, tag: &Identifier) -> Option<Vec<VarInt>> {
+        let tags = self.tags.read().unwrap();
+        tags.get(registry).and_then(|tags| tags.get(tag)).cloned()
+    }
+
+    pub fn contains(&self, registry: &Identifier, tag: &Identifier, entry: i32) -> bool {
+        self.get(registry, tag)
+            .is_some_and(|entries| entries.contains(&VarInt::new(entry)))
+    }
+
+    /// Snapshot of all registries in the `Update Tags` packet layout.
+    pub fn registry_tags(&self) -> Vec<RegistryTags> {
+        let tags = self.tags.read().unwrap();
+        tags.iter()
+            .map(|(registry, tags)| RegistryTags {
+                registry: registry.clone(),
+                tags: tags
+                    .iter()
+                    .map(|(name, entries)| Tag {
+                        name: name.clone(),
+                        entries: entries.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct GeneratedRegistry {
+    entries: BTreeMap<String, GeneratedEntry>,
+}
+
+#[derive(Deserialize)]
+struct GeneratedEntry {
+    protocol_id: i32,
+}
+
+#[derive(Deserialize)]
+struct TagFile {
+    values: Vec<TagValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagValue {
+    Id(String),
+    Entry {
+        id: String,
+        #[serde(default = "required_by_default")]
+        required: bool,
+    },
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+/// Reads `data/<namespace>/tags/<tags_dir>/**/*.json` of every namespace, keyed by tag name.
+fn generated_tag_files(data_dir: &Path, tags_dir: &str) -> anyhow::Result<BTreeMap<String, TagFile>> {
+    let mut files = BTreeMap::new();
+    for namespace in fs::read_dir(data_dir).with_context(|| format!("Failed to read {}", data_dir.display()))? {
+        let namespace = namespace?;
+        let root = namespace.path().join("tags").join(tags_dir);
+        if root.is_dir() {
+            let namespace = namespace.file_name().to_string_lossy().into_owned();
+            collect_tag_files(&root, &root, &namespace, &mut files)?;
+        }
+    }
+    Ok(files)
+}
+
+fn collect_tag_files(
+    root: &Path,
+    dir: &Path,
+    namespace: &str,
+    files: &mut BTreeMap<String, TagFile>,
+) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_tag_files(root, &path, namespace, files)?;
+        } else if path.extension().is_some_and(|extension| extension == "json") {
+            let name = path.strip_prefix(root)?.with_extension("");
+            let name = name.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>();
+            let content = fs::read_to_string(&path)?;
+            let file = serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+            files.insert(format!("{namespace}:{}", name.join("/")), file);
+        }
+    }
+    Ok(())
+}
+
+fn resolve_tag(
+    tag: &str,
+    files: &BTreeMap<String, TagFile>,
+    registry: &GeneratedRegistry,
+    resolved: &mut BTreeMap<String, Vec<i32>>,
+    visiting: &mut BTreeSet<String>,
+) -> anyhow::Result<Vec<i32>> {
+    if let Some(entries) = resolved.get(tag) {
+        return Ok(entries.clone());
+    }
+    if !visiting.insert(tag.to_string()) {
+        bail!("Tag #{tag} references itself");
+    }
+    let file = files.get(tag).ok_or_else(|| anyhow!("Unknown tag #{tag}"))?;
+
+    let mut entries = Vec::new();
+    for value in &file.values {
+        let (id, required) = match value {
+            TagValue::Id(id) => (id.as_str(), true),
+            TagValue::Entry { id, required } => (id.as_str(), *required),
+        };
+
+        let ids = match id.strip_prefix('#') {
+            Some(_) if !required && !files.contains_key(&id[1..]) => continue,
+            Some(nested) => resolve_tag(nested, files, registry, resolved, visiting)?,
+            None => match registry.entries.get(id) {
+                Some(entry) => vec![entry.protocol_id],
+                None if required => bail!("Unknown entry {id}"),
+                None => continue,
+            },
+        };
+        for id in ids {
+            if !entries.contains(&id) {
+                entries.push(id);
+            }
+        }
+    }
+
+    visiting.remove(tag);
+    resolved.insert(tag.to_string(), entries.clone());
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_tags_are_extendable() {
+        let registry = TagRegistry::bundled().unwrap();
+        let (fluid, water) = ("minecraft:fluid".into(), "minecraft:water".into());
+
+        assert!(registry.contains(&fluid, &water, 2));
+
+        registry.extend(fluid.clone(), water.clone(), [2, 42]);
+
+        assert_eq!(
+            registry.get(&fluid, &water).unwrap(),
+            vec![VarInt::new(1), VarInt::new(2), VarInt::new(42)]
+        );
+    }
+
+    #[test]
+    fn test_generated_climbable() {
+        let dir = std::env::temp_dir().join(format!("generated-tags-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "reports/registries.json",
+            r#"{
+                "minecraft:block": {"entries": {
+                    "minecraft:air": {"protocol_id": 0},
+                    "minecraft:ladder": {"protocol_id": 10},
+                    "minecraft:vine": {"protocol_id": 11},
+                    "minecraft:scaffolding": {"protocol_id": 12}
+                }},
+                "minecraft:item": {"entries": {}},
+                "minecraft:fluid": {"entries": {}},
+                "minecraft:entity_type": {"entries": {}}
+            }"#,
+        );
+        write(
+            "data/minecraft/tags/block/climbable.json",
+            r##"{"values": ["minecraft:ladder", "#minecraft:vines", {"id": "minecraft:rope", "required": false}]}"##,
+        );
+        write(
+            "data/minecraft/tags/block/vines.json",
+            r#"{"values": ["minecraft:vine", "minecraft:scaffolding", "minecraft:vine"]}"#,
+        );
+
+        let generated = TagRegistry::from_generated(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let registry = TagRegistry::from_json(&generated.unwrap().to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            registry.get(&"minecraft:block".into(), &"minecraft:climbable".into()).unwrap(),
+            vec![VarInt::new(10), VarInt::new(11), VarInt::new(12)]
+        );
+        assert!(registry.get(&"minecraft:item".into(), &"minecraft:climbable".into()).is_none());
+    }
+}
@@ -0,0 +1,111 @@
+mod builder;
+mod login;
+mod player_registry;
+mod sessions;
+mod status;
+
+use crate::access::AccessLists;
+use crate::config::ServerConfig;
+use crate::connection::channels::ChannelRegistry;
+use crate::connection::resource_pack::ResourcePack;
+use crate::diagnostics::{Profiler, Watchdog};
+use crate::game::{Game, Scheduler};
+use crate::plugin::{Event, EventBus, Plugin};
+use crate::protocol::types::{Identifier, TextComponent};
+use crate::registry::TagRegistry;
+use crate::storage::Storage;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::info;
+
+pub use builder::ServerBuilder;
+
+pub use login::{is_valid_username, offline_uuid};
+pub use player_registry::{JoinedPlayer, OnlinePlayer, PlayerRegistry};
+pub use sessions::{DuplicateLoginPolicy, RegisterError, Session, SessionCommand, SessionRegistry};
+pub use status::{load_favicon, ServerStatus, ServerStatusBuilder, StatusCache};
+
+const STATUS_CACHE_TTL: Duration = Duration::from_secs(1);
+const STATUS_SAMPLE_SIZE: usize = 12;
+
+/// State shared by all client connections.
+pub struct Server {
+    pub config: ServerConfig,
+    pub brand: String,
+    pub tags: TagRegistry,
+    pub feature_flags: Vec<Identifier>,
+    pub channels: ChannelRegistry,
+    pub resource_pack: Option<ResourcePack>,
+    pub sessions: SessionRegistry,
+    pub players: PlayerRegistry,
+    pub storage: Arc<dyn Storage>,
+    pub access: AccessLists,
+    pub events: EventBus,
+    pub game: Game,
+    pub scheduler: Scheduler,
+    pub watchdog: Watchdog,
+    pub profiler: Profiler,
+    plugins: Vec<Box<dyn Plugin>>,
+    favicon: Option<String>,
+    status_cache: StatusCache,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig, storage: Arc<dyn Storage>) -> anyhow::Result<Self> {
+        Self::builder(config, storage).build()
+    }
+
+    pub fn builder(config: ServerConfig, storage: Arc<dyn Storage>) -> ServerBuilder {
+        ServerBuilder::new(config, storage)
+    }
+
+    /// Runs the plugin handlers of the event.
+    pub fn fire<E: Event>(&self, event: E) -> E {
+        self.events.fire(self, event)
+    }
+
+    pub fn plugin_names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    /// Lets plugins clean up once the server has stopped.
+    pub fn disable_plugins(&self) {
+        for plugin in self.plugins.iter().rev() {
+            plugin.disable(self);
+            info!(plugin = plugin.name(), "Disabled plugin");
+        }
+    }
+
+    /// Server list status JSON with live player counts.
+    pub fn status_json(&self) -> String {
+        self.status_cache.get_or_build(|| {
+            ServerStatus::builder()
+                .players(self.players.len(), self.config.gameplay.max_players)
+                .sample(self.players.sample(STATUS_SAMPLE_SIZE))
+                .description(self.config.motd.text.as_str())
+                .favicon(self.favicon.clone())
+                .enforces_secure_chat(self.config.gameplay.enforce_secure_chat)
+                .build()
+                .to_json()
+        })
+    }
+
+    /// Kicks every player and signals the listeners to stop.
+    pub fn shutdown(&self) {
+        self.sessions
+            .broadcast(|| SessionCommand::Disconnect(TextComponent::new("Server closed")));
+        self.shutdown.send_replace(true);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Resolves once `shutdown` was called.
+    pub async fn wait_for_shutdown(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        let _ = shutdown.wait_for(|&shutdown| shutdown).await;
+    }
+}
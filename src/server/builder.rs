@@ -0,0 +1,82 @@
+use super::{load_favicon, PlayerRegistry, Server, SessionRegistry, StatusCache, STATUS_CACHE_TTL};
+use crate::access::AccessLists;
+use crate::config::ServerConfig;
+use crate::connection::channels::ChannelRegistry;
+use crate::diagnostics::{Profiler, Watchdog};
+use crate::game::{Game, Scheduler};
+use crate::plugin::{EventBus, Plugin};
+use crate::registry::TagRegistry;
+use crate::storage::Storage;
+use anyhow::Context;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::info;
+
+/// Assembles a server with the plugins compiled into the binary.
+pub struct ServerBuilder {
+    config: ServerConfig,
+    storage: Arc<dyn Storage>,
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl ServerBuilder {
+    pub fn new(config: ServerConfig, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            config,
+            storage,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Plugins are enabled in the order they are added.
+    pub fn plugin(mut self, plugin: impl Plugin) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Server> {
+        let config = self.config;
+        let resource_pack = config
+            .resource_pack
+            .as_ref()
+            .map(|pack| pack.to_resource_pack())
+            .transpose()?;
+
+        let favicon = match &config.motd.favicon {
+            Some(path) => Some(load_favicon(path).context("Failed to load favicon")?),
+            None => None,
+        };
+
+        let watchdog = Watchdog::new(Duration::from_millis(config.diagnostics.slow_threshold_ms));
+
+        let mut events = EventBus::new();
+        for plugin in &self.plugins {
+            events.set_owner(plugin.name());
+            plugin.enable(&mut events);
+            info!(plugin = plugin.name(), "Enabled plugin");
+        }
+
+        Ok(Server {
+            config,
+            brand: "minecraft-server".into(),
+            tags: TagRegistry::bundled()?,
+            feature_flags: vec!["minecraft:vanilla".into()],
+            channels: ChannelRegistry::new(),
+            resource_pack,
+            sessions: SessionRegistry::new(),
+            players: PlayerRegistry::new(),
+            access: AccessLists::new(self.storage.clone()),
+            storage: self.storage,
+            events,
+            game: Game::new(),
+            scheduler: Scheduler::new(),
+            watchdog,
+            profiler: Profiler::new(),
+            plugins: self.plugins,
+            favicon,
+            status_cache: StatusCache::new(STATUS_CACHE_TTL),
+            shutdown: watch::Sender::new(false),
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use md5::{Digest, Md5};
+use uuid::{Builder, Uuid};
+
+const MIN_USERNAME_LENGTH: usize = 3;
+const MAX_USERNAME_LENGTH: usize = 16;
+
+/// Vanilla usernames are 3-16 characters of `A-Z`, `a-z`, `0-9` and `_`.
+pub fn is_valid_username(name: &str) -> bool {
+    (MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// UUID an offline mode server assigns, i.e. Java's `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{name}").as_bytes());
+    Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid() {
+        assert_eq!(
+            offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+
+    #[test]
+    fn test_username_validation() {
+        assert!(is_valid_username("wristylotus"));
+        assert!(is_valid_username("Steve_42"));
+        assert!(!is_valid_username("ab"));
+        assert!(!is_valid_username("seventeen_chars__"));
+        assert!(!is_valid_username("bad name"));
+        assert!(!is_valid_username("ünicode"));
+    }
+}
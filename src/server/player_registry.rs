@@ -0,0 +1,185 @@
+use crate::protocol::types::GameProfile;
+use rand::seq::IteratorRandom;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::ops::Deref;
+use std::sync::{Arc, RwLock, Weak};
+use uuid::Uuid;
+
+/// A player in the Play state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnlinePlayer {
+    pub entity_id: i32,
+    pub profile: GameProfile,
+}
+
+#[derive(Debug, Default)]
+struct Players {
+    by_uuid: HashMap<Uuid, OnlinePlayer>,
+    by_name: HashMap<String, Uuid>,
+    by_entity_id: HashMap<i32, Uuid>,
+}
+
+impl Players {
+    fn remove(&mut self, uuid: &Uuid, entity_id: i32) -> Option<OnlinePlayer> {
+        if self.by_uuid.get(uuid)?.entity_id != entity_id {
+            return None;
+        }
+
+        let player = self.by_uuid.remove(uuid)?;
+        self.by_name.remove(&player.profile.name.to_lowercase());
+        self.by_entity_id.remove(&player.entity_id);
+        Some(player)
+    }
+}
+
+/// Player added to the registry, owned by its connection. Dropping it removes the player,
+/// even when the connection task panics or is cancelled.
+#[derive(Debug)]
+pub struct JoinedPlayer {
+    player: OnlinePlayer,
+    registry: Weak<RwLock<Players>>,
+}
+
+impl Deref for JoinedPlayer {
+    type Target = OnlinePlayer;
+
+    fn deref(&self) -> &OnlinePlayer {
+        &self.player
+    }
+}
+
+impl Drop for JoinedPlayer {
+    fn drop(&mut self) {
+        if let Some(players) = self.registry.upgrade() {
+            players
+                .write()
+                .unwrap()
+                .remove(&self.player.profile.uuid, self.player.entity_id);
+        }
+    }
+}
+
+/// Online players indexed by UUID, lowercase name and entity ID.
+#[derive(Debug)]
+pub struct PlayerRegistry {
+    players: Arc<RwLock<Players>>,
+    next_entity_id: AtomicI32,
+}
+
+impl Default for PlayerRegistry {
+    fn default() -> Self {
+        Self {
+            players: Arc::default(),
+            next_entity_id: AtomicI32::new(1),
+        }
+    }
+}
+
+impl PlayerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates an entity ID for the player and adds them to the registry until the returned player is dropped.
+    pub fn join(&self, profile: GameProfile) -> JoinedPlayer {
+        let player = OnlinePlayer {
+            entity_id: self.next_entity_id.fetch_add(1, Ordering::Relaxed),
+            profile,
+        };
+
+        let mut players = self.players.write().unwrap();
+        if let Some(previous) = players.by_uuid.remove(&player.profile.uuid) {
+            players.by_entity_id.remove(&previous.entity_id);
+        }
+        players
+            .by_name
+            .insert(player.profile.name.to_lowercase(), player.profile.uuid);
+        players.by_entity_id.insert(player.entity_id, player.profile.uuid);
+        players.by_uuid.insert(player.profile.uuid, player.clone());
+
+        JoinedPlayer {
+            player,
+            registry: Arc::downgrade(&self.players),
+        }
+    }
+
+    /// Removes the player unless the UUID was taken over by a newer session with another entity ID.
+    pub fn leave(&self, uuid: &Uuid, entity_id: i32) -> Option<OnlinePlayer> {
+        self.players.write().unwrap().remove(uuid, entity_id)
+    }
+
+    pub fn by_uuid(&self, uuid: &Uuid) -> Option<OnlinePlayer> {
+        self.players.read().unwrap().by_uuid.get(uuid).cloned()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<OnlinePlayer> {
+        let players = self.players.read().unwrap();
+        let uuid = players.by_name.get(&name.to_lowercase())?;
+        players.by_uuid.get(uuid).cloned()
+    }
+
+    pub fn by_entity_id(&self, entity_id: i32) -> Option<OnlinePlayer> {
+        let players = self.players.read().unwrap();
+        let uuid = players.by_entity_id.get(&entity_id)?;
+        players.by_uuid.get(uuid).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.read().unwrap().by_uuid.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn all(&self) -> Vec<OnlinePlayer> {
+        self.players.read().unwrap().by_uuid.values().cloned().collect()
+    }
+
+    /// Up to `amount` randomly chosen player profiles.
+    pub fn sample(&self, amount: usize) -> Vec<GameProfile> {
+        let players = self.players.read().unwrap();
+        players
+            .by_uuid
+            .values()
+            .map(|player| player.profile.clone())
+            .sample(&mut rand::rng(), amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejoin_is_not_removed_by_old_session() {
+        let registry = PlayerRegistry::new();
+        let profile = GameProfile::new(Uuid::new_v4(), "Steve");
+
+        let old = registry.join(profile.clone());
+        let new = registry.join(profile.clone());
+
+        assert!(registry.leave(&profile.uuid, old.entity_id).is_none());
+        assert_eq!(registry.by_name("steve").as_ref(), Some(&*new));
+        assert_eq!(registry.by_entity_id(new.entity_id).as_ref(), Some(&*new));
+        assert!(registry.by_entity_id(old.entity_id).is_none());
+
+        assert_eq!(registry.leave(&profile.uuid, new.entity_id).as_ref(), Some(&*new));
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_player_leaves() {
+        let registry = PlayerRegistry::new();
+        let profile = GameProfile::new(Uuid::new_v4(), "Steve");
+
+        let old = registry.join(profile.clone());
+        let new = registry.join(profile);
+
+        drop(old);
+        assert_eq!(registry.len(), 1);
+        drop(new);
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::protocol::types::{GameProfile, Location, TextComponent, VarInt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+/// What to do when a player logs in while a session with the same name or UUID exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateLoginPolicy {
+    #[default]
+    KickExisting,
+    RejectNew,
+}
+
+/// Commands sent to a connection task by the rest of the server.
+#[derive(Debug)]
+pub enum SessionCommand {
+    Disconnect(TextComponent),
+    /// System chat message, dropped unless the player is in Play.
+    Message(TextComponent),
+    /// Message above the hotbar, dropped unless the player is in Play.
+    ActionBar(TextComponent),
+    /// Moves a player in Play, who must confirm the teleport.
+    Teleport(Location),
+    AcknowledgeBlockChange(VarInt),
+}
+
+type Sessions = Mutex<HashMap<Uuid, SessionHandle>>;
+
+/// Why a login was refused a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterError {
+    /// Another session of the player exists under `DuplicateLoginPolicy::RejectNew`.
+    AlreadyLoggedIn,
+    Full,
+}
+
+impl RegisterError {
+    /// Disconnect message shown to the player.
+    pub fn message(&self) -> TextComponent {
+        match self {
+            RegisterError::AlreadyLoggedIn => TextComponent::new("You are already logged in to this server."),
+            RegisterError::Full => TextComponent::new("Server is full"),
+        }
+    }
+
+    /// Reason label of the failed login metric.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RegisterError::AlreadyLoggedIn => "duplicate_login",
+            RegisterError::Full => "server_full",
+        }
+    }
+}
+
+/// Registration of a logged in player, owned by its connection. Dropping it unregisters the player,
+/// even when the connection task panics or is cancelled.
+#[derive(Debug)]
+pub struct Session {
+    pub id: u64,
+    pub profile: GameProfile,
+    pub commands: UnboundedReceiver<SessionCommand>,
+    registry: Weak<Sessions>,
+}
+
+impl Drop for Session {
+    /// Removes the session unless it was already replaced by a newer login.
+    fn drop(&mut self) {
+        let Some(sessions) = self.registry.upgrade() else {
+            return;
+        };
+        let mut sessions = sessions.lock().unwrap();
+        if sessions
+            .get(&self.profile.uuid)
+            .is_some_and(|handle| handle.id == self.id)
+        {
+            sessions.remove(&self.profile.uuid);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SessionHandle {
+    id: u64,
+    profile: GameProfile,
+    commands: UnboundedSender<SessionCommand>,
+}
+
+/// All logged in players, from `Login Success` until their connection is closed.
+#[derive(Debug, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Sessions>,
+    next_id: AtomicU64,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the player, applying the policy to an existing session of the same player.
+    ///
+    /// Sessions take a slot from `Login Success` on, so logins in progress count towards `max_players`.
+    /// Sessions replaced by this login don't count, `None` registers the player even on a full server.
+    pub fn register(
+        &self,
+        profile: GameProfile,
+        policy: DuplicateLoginPolicy,
+        max_players: Option<u32>,
+    ) -> Result<Session, RegisterError> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let duplicates: Vec<Uuid> = sessions
+            .values()
+            .filter(|session| {
+                session.profile.uuid == profile.uuid || session.profile.name.eq_ignore_ascii_case(&profile.name)
+            })
+            .map(|session| session.profile.uuid)
+            .collect();
+
+        if !duplicates.is_empty() && policy == DuplicateLoginPolicy::RejectNew {
+            return Err(RegisterError::AlreadyLoggedIn);
+        }
+        if max_players.is_some_and(|max| sessions.len() - duplicates.len() >= max as usize) {
+            return Err(RegisterError::Full);
+        }
+        for uuid in duplicates {
+            if let Some(existing) = sessions.remove(&uuid) {
+                let reason = TextComponent::new("You logged in from another location.");
+                let _ = existing.commands.send(SessionCommand::Disconnect(reason));
+            }
+        }
+
+        let (tx, rx) = unbounded_channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        sessions.insert(
+            profile.uuid,
+            SessionHandle {
+                id,
+                profile: profile.clone(),
+                commands: tx,
+            },
+        );
+
+        Ok(Session {
+            id,
+            profile,
+            commands: rx,
+            registry: Arc::downgrade(&self.sessions),
+        })
+    }
+
+    pub fn send(&self, uuid: &Uuid, command: SessionCommand) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(uuid)
+            .is_some_and(|session| session.commands.send(command).is_ok())
+    }
+
+    /// Sends the command to every session, returns how many received it.
+    pub fn broadcast(&self, command: impl Fn() -> SessionCommand) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .filter(|session| session.commands.send(command()).is_ok())
+            .count()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<GameProfile> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .find(|session| session.profile.name.eq_ignore_ascii_case(name))
+            .map(|session| session.profile.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_session_is_unregistered() {
+        let registry = SessionRegistry::new();
+        let profile = GameProfile::new(Uuid::new_v4(), "Steve");
+
+        let session = registry.register(profile.clone(), DuplicateLoginPolicy::RejectNew, None).unwrap();
+        assert!(registry.register(profile.clone(), DuplicateLoginPolicy::RejectNew, None).is_err());
+
+        drop(session);
+        assert!(registry.is_empty());
+        assert!(registry.register(profile, DuplicateLoginPolicy::RejectNew, None).is_ok());
+    }
+
+    #[test]
+    fn test_replaced_session_is_kept() {
+        let registry = SessionRegistry::new();
+        let profile = GameProfile::new(Uuid::new_v4(), "Steve");
+
+        let old = registry.register(profile.clone(), DuplicateLoginPolicy::KickExisting, None).unwrap();
+        let new = registry.register(profile.clone(), DuplicateLoginPolicy::KickExisting, None).unwrap();
+
+        drop(old);
+        assert_eq!(registry.find_by_name("steve"), Some(profile));
+        drop(new);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_max_players() {
+        let registry = SessionRegistry::new();
+        let steve = GameProfile::new(Uuid::new_v4(), "Steve");
+        let alex = GameProfile::new(Uuid::new_v4(), "Alex");
+        let policy = DuplicateLoginPolicy::KickExisting;
+
+        let _steve = registry.register(steve.clone(), policy, Some(1)).unwrap();
+        assert_eq!(
+            registry.register(alex.clone(), policy, Some(1)).unwrap_err(),
+            RegisterError::Full
+        );
+        assert!(registry.register(alex, policy, None).is_ok());
+
+        // Rejoining replaces the old session instead of taking another slot
+        assert!(registry.register(steve, policy, Some(2)).is_ok());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::protocol::types::{GameProfile, TextComponent};
+use crate::protocol::{PROTOCOL_VERSION, VERSION_NAME};
+use anyhow::bail;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const FAVICON_SIZE: u32 = 64;
+
+/// Server list ping response.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub version: StatusVersion,
+    pub players: StatusPlayers,
+    pub description: TextComponent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusPlayers {
+    pub max: u32,
+    pub online: usize,
+    pub sample: Vec<StatusPlayer>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusPlayer {
+    pub name: String,
+    pub id: String,
+}
+
+impl ServerStatus {
+    pub fn builder() -> ServerStatusBuilder {
+        ServerStatusBuilder::default()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Server status is always serializable")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ServerStatusBuilder {
+    max_players: u32,
+    online_players: usize,
+    sample: Vec<GameProfile>,
+    description: TextComponent,
+    favicon: Option<String>,
+    enforces_secure_chat: bool,
+}
+
+impl ServerStatusBuilder {
+    pub fn players(mut self, online: usize, max: u32) -> Self {
+        self.online_players = online;
+        self.max_players = max;
+        self
+    }
+
+    pub fn sample(mut self, sample: Vec<GameProfile>) -> Self {
+        self.sample = sample;
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<TextComponent>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Base64 data URI of a 64x64 PNG, see [`load_favicon`].
+    pub fn favicon(mut self, favicon: Option<String>) -> Self {
+        self.favicon = favicon;
+        self
+    }
+
+    pub fn enforces_secure_chat(mut self, enforces_secure_chat: bool) -> Self {
+        self.enforces_secure_chat = enforces_secure_chat;
+        self
+    }
+
+    pub fn build(self) -> ServerStatus {
+        ServerStatus {
+            version: StatusVersion {
+                name: VERSION_NAME.into(),
+                protocol: PROTOCOL_VERSION,
+            },
+            players: StatusPlayers {
+                max: self.max_players,
+                online: self.online_players,
+                sample: self
+                    .sample
+                    .into_iter()
+                    .map(|profile| StatusPlayer {
+                        name: profile.name,
+                        id: profile.uuid.hyphenated().to_string(),
+                    })
+                    .collect(),
+            },
+            description: self.description,
+            favicon: self.favicon,
+            enforces_secure_chat: self.enforces_secure_chat,
+        }
+    }
+}
+
+/// Reads a 64x64 PNG and encodes it as a `data:image/png;base64,` URI.
+pub fn load_favicon(path: &Path) -> anyhow::Result<String> {
+    let png = std::fs::read(path)?;
+
+    // IHDR is always the first chunk: length(4) + type(4) + width(4) + height(4)
+    if png.len() < 24 || !png.starts_with(PNG_SIGNATURE) || &png[12..16] != b"IHDR" {
+        bail!("Favicon '{}' is not a PNG image", path.display());
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into()?);
+    let height = u32::from_be_bytes(png[20..24].try_into()?);
+    if (width, height) != (FAVICON_SIZE, FAVICON_SIZE) {
+        bail!("Favicon '{}' must be 64x64, got {}x{}", path.display(), width, height);
+    }
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+/// Keeps the serialized status for a short time so ping floods don't rebuild it on every request.
+#[derive(Debug)]
+pub struct StatusCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, String)>>,
+}
+
+impl StatusCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub fn get_or_build(&self, build: impl FnOnce() -> String) -> String {
+        let mut cached = self.cached.lock().unwrap();
+        match cached.as_ref() {
+            Some((created, status)) if created.elapsed() < self.ttl => status.clone(),
+            _ => {
+                let status = build();
+                *cached = Some((Instant::now(), status.clone()));
+                status
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_status_json() {
+        let status = ServerStatus::builder()
+            .players(1, 20)
+            .sample(vec![GameProfile::new(Uuid::nil(), "Steve")])
+            .description("Hello")
+            .build();
+
+        assert_eq!(
+            status.to_json(),
+            format!(
+                r#"{{"version":{{"name":"{VERSION_NAME}","protocol":{PROTOCOL_VERSION}}},"players":{{"max":20,"online":1,"sample":[{{"name":"Steve","id":"00000000-0000-0000-0000-000000000000"}}]}},"description":{{"text":"Hello"}},"enforcesSecureChat":false}}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,65 @@
+mod memory;
+mod redis;
+
+use crate::access::{AccessList, BanEntry, BanKind};
+use crate::config::{ServerConfig, StorageBackend};
+use deadpool_redis::Status;
+use std::pin::Pin;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub use memory::MemoryStorage;
+pub use redis::RedisStorage;
+
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'a>>;
+
+/// Persistent data of the server: access lists, player data and world data.
+///
+/// Names and ban targets are stored as given, `AccessLists` normalizes them.
+pub trait Storage: Send + Sync + 'static {
+    /// Name of the backend, for logs.
+    fn name(&self) -> &'static str;
+
+    /// False while the backend is unreachable. Operations fail fast and new logins are refused until it recovers.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Connections of the pool, for backends using one.
+    fn pool_status(&self) -> Option<Status> {
+        None
+    }
+
+    fn list_add<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool>;
+
+    fn list_remove<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool>;
+
+    fn list_contains<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool>;
+
+    fn list_members(&self, list: AccessList) -> StorageFuture<'_, Vec<String>>;
+
+    fn ban<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, Option<BanEntry>>;
+
+    /// Replaces the ban of the same target.
+    fn ban_add(&self, kind: BanKind, entry: BanEntry) -> StorageFuture<'_, ()>;
+
+    fn ban_remove<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, bool>;
+
+    fn bans(&self, kind: BanKind) -> StorageFuture<'_, Vec<BanEntry>>;
+
+    fn player_data(&self, uuid: Uuid) -> StorageFuture<'_, Option<Vec<u8>>>;
+
+    fn save_player_data(&self, uuid: Uuid, data: Vec<u8>) -> StorageFuture<'_, ()>;
+
+    fn world_data<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>>;
+
+    fn save_world_data<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()>;
+}
+
+/// Storage of the configured backend. Redis is connected lazily and retried in the background.
+pub fn open(config: &ServerConfig) -> anyhow::Result<Arc<dyn Storage>> {
+    Ok(match config.storage.backend {
+        StorageBackend::Redis => Arc::new(RedisStorage::new(&config.redis)?),
+        StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+    })
+}
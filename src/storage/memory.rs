@@ -0,0 +1,93 @@
+use super::{Storage, StorageFuture};
+use crate::access::{AccessList, BanEntry, BanKind};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Storage kept in memory, lost when the server stops. Meant for development and tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    lists: Mutex<HashMap<AccessList, BTreeSet<String>>>,
+    bans: Mutex<HashMap<(BanKind, String), BanEntry>>,
+    players: Mutex<HashMap<Uuid, Vec<u8>>>,
+    world: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn ready<'a, T: Send + 'a>(value: T) -> StorageFuture<'a, T> {
+    Box::pin(std::future::ready(Ok(value)))
+}
+
+impl Storage for MemoryStorage {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn list_add<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        let mut lists = self.lists.lock().unwrap();
+        ready(lists.entry(list).or_default().insert(name.to_string()))
+    }
+
+    fn list_remove<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        let mut lists = self.lists.lock().unwrap();
+        ready(lists.get_mut(&list).is_some_and(|members| members.remove(name)))
+    }
+
+    fn list_contains<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        let lists = self.lists.lock().unwrap();
+        ready(lists.get(&list).is_some_and(|members| members.contains(name)))
+    }
+
+    fn list_members(&self, list: AccessList) -> StorageFuture<'_, Vec<String>> {
+        let lists = self.lists.lock().unwrap();
+        ready(lists.get(&list).into_iter().flatten().cloned().collect())
+    }
+
+    fn ban<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, Option<BanEntry>> {
+        let bans = self.bans.lock().unwrap();
+        ready(bans.get(&(kind, target.to_string())).cloned())
+    }
+
+    fn ban_add(&self, kind: BanKind, entry: BanEntry) -> StorageFuture<'_, ()> {
+        self.bans.lock().unwrap().insert((kind, entry.target.clone()), entry);
+        ready(())
+    }
+
+    fn ban_remove<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, bool> {
+        let mut bans = self.bans.lock().unwrap();
+        ready(bans.remove(&(kind, target.to_string())).is_some())
+    }
+
+    fn bans(&self, kind: BanKind) -> StorageFuture<'_, Vec<BanEntry>> {
+        let bans = self.bans.lock().unwrap();
+        ready(
+            bans.iter()
+                .filter(|((other, _), _)| *other == kind)
+                .map(|(_, entry)| entry.clone())
+                .collect(),
+        )
+    }
+
+    fn player_data(&self, uuid: Uuid) -> StorageFuture<'_, Option<Vec<u8>>> {
+        ready(self.players.lock().unwrap().get(&uuid).cloned())
+    }
+
+    fn save_player_data(&self, uuid: Uuid, data: Vec<u8>) -> StorageFuture<'_, ()> {
+        self.players.lock().unwrap().insert(uuid, data);
+        ready(())
+    }
+
+    fn world_data<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>> {
+        ready(self.world.lock().unwrap().get(key).cloned())
+    }
+
+    fn save_world_data<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()> {
+        self.world.lock().unwrap().insert(key.to_string(), data);
+        ready(())
+    }
+}
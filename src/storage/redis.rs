@@ -0,0 +1,251 @@
+use super::{Storage, StorageFuture};
+use crate::access::{AccessList, BanEntry, BanKind};
+use crate::config::RedisConfig;
+use crate::metrics::METRICS;
+use anyhow::bail;
+use deadpool_redis::redis::{self, AsyncCommands, RedisError};
+use deadpool_redis::{Config, Connection, Pool, Runtime, Status};
+use prometheus::HistogramTimer;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Getting a connection fails after this long, so operations don't hang while Redis is unreachable.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+fn list_key(list: AccessList) -> &'static str {
+    match list {
+        AccessList::Whitelist => "mc:whitelist",
+        AccessList::Ops => "mc:ops",
+    }
+}
+
+fn ban_key(kind: BanKind) -> &'static str {
+    match kind {
+        BanKind::Uuid => "mc:bans:uuid",
+        BanKind::Name => "mc:bans:name",
+        BanKind::Ip => "mc:bans:ip",
+    }
+}
+
+fn player_key(uuid: Uuid) -> String {
+    format!("mc:player:{}", uuid)
+}
+
+fn world_key(key: &str) -> String {
+    format!("mc:world:{}", key)
+}
+
+struct Health {
+    available: AtomicBool,
+    /// Wakes the monitor to check the connection right away after an operation failed.
+    failed: Notify,
+}
+
+/// Storage in Redis, reconnecting with exponential backoff while it is unreachable.
+pub struct RedisStorage {
+    pool: Pool,
+    health: Arc<Health>,
+}
+
+impl RedisStorage {
+    /// Must be created in a Tokio runtime, which runs the health monitor until the storage is dropped.
+    pub fn new(config: &RedisConfig) -> anyhow::Result<Self> {
+        let pool = Config::from_url(config.url.clone())
+            .builder()?
+            .max_size(config.pool_size)
+            .wait_timeout(Some(CONNECTION_TIMEOUT))
+            .create_timeout(Some(CONNECTION_TIMEOUT))
+            .runtime(Runtime::Tokio1)
+            .build()?;
+
+        // Assume Redis is up until the first check says otherwise
+        let health = Arc::new(Health {
+            available: AtomicBool::new(true),
+            failed: Notify::new(),
+        });
+        tokio::spawn(monitor(pool.clone(), Arc::downgrade(&health)));
+        Ok(Self { pool, health })
+    }
+
+    /// Pooled connection and the latency timer of the operation.
+    async fn connection(&self, operation: &str) -> anyhow::Result<(Connection, HistogramTimer)> {
+        if !self.is_available() {
+            bail!("Redis is unavailable");
+        }
+        let timer = METRICS.redis_latency.with_label_values(&[operation]).start_timer();
+        match self.pool.get().await {
+            Ok(conn) => Ok((conn, timer)),
+            Err(e) => {
+                self.mark_failed();
+                bail!("Failed to connect to Redis: {}", e)
+            }
+        }
+    }
+
+    /// Marks Redis unavailable on connection errors.
+    fn check<T>(&self, result: Result<T, RedisError>) -> anyhow::Result<T> {
+        if let Err(e) = &result
+            && (e.is_io_error() || e.is_connection_dropped() || e.is_connection_refusal() || e.is_timeout())
+        {
+            self.mark_failed();
+        }
+        Ok(result?)
+    }
+
+    fn mark_failed(&self) {
+        if self.health.available.swap(false, Ordering::AcqRel) {
+            warn!("Lost connection to Redis, refusing new logins until it recovers");
+        }
+        self.health.failed.notify_one();
+    }
+}
+
+/// Pings Redis periodically while it is available, and with exponential backoff until it recovers.
+async fn monitor(pool: Pool, health: Weak<Health>) {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let result = ping(&pool).await;
+        let Some(health) = health.upgrade() else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                backoff = INITIAL_BACKOFF;
+                if !health.available.swap(true, Ordering::AcqRel) {
+                    info!("Connection to Redis restored");
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(HEALTH_CHECK_INTERVAL) => {}
+                    _ = health.failed.notified() => {}
+                }
+            }
+            Err(e) => {
+                if health.available.swap(false, Ordering::AcqRel) {
+                    warn!(error = %e, "Redis is unavailable, refusing new logins until it recovers");
+                } else {
+                    debug!(error = %e, retry_in = ?backoff, "Redis is still unavailable");
+                }
+                drop(health);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+async fn ping(pool: &Pool) -> anyhow::Result<()> {
+    let mut conn = pool.get().await?;
+    let _: String = tokio::time::timeout(CONNECTION_TIMEOUT, redis::cmd("PING").query_async(&mut conn)).await??;
+    Ok(())
+}
+
+impl Storage for RedisStorage {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn is_available(&self) -> bool {
+        self.health.available.load(Ordering::Acquire)
+    }
+
+    fn pool_status(&self) -> Option<Status> {
+        Some(self.pool.status())
+    }
+
+    fn list_add<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("list_add").await?;
+            self.check(conn.sadd(list_key(list), name).await)
+        })
+    }
+
+    fn list_remove<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("list_remove").await?;
+            self.check(conn.srem(list_key(list), name).await)
+        })
+    }
+
+    fn list_contains<'a>(&'a self, list: AccessList, name: &'a str) -> StorageFuture<'a, bool> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("list_contains").await?;
+            self.check(conn.sismember(list_key(list), name).await)
+        })
+    }
+
+    fn list_members(&self, list: AccessList) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("list_members").await?;
+            self.check(conn.smembers(list_key(list)).await)
+        })
+    }
+
+    fn ban<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, Option<BanEntry>> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("ban").await?;
+            let entry: Option<String> = self.check(conn.hget(ban_key(kind), target).await)?;
+            Ok(entry.map(|entry| serde_json::from_str(&entry)).transpose()?)
+        })
+    }
+
+    fn ban_add(&self, kind: BanKind, entry: BanEntry) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            let value = serde_json::to_string(&entry)?;
+            let (mut conn, _timer) = self.connection("ban_add").await?;
+            self.check(conn.hset(ban_key(kind), &entry.target, value).await)
+        })
+    }
+
+    fn ban_remove<'a>(&'a self, kind: BanKind, target: &'a str) -> StorageFuture<'a, bool> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("ban_remove").await?;
+            self.check(conn.hdel(ban_key(kind), target).await)
+        })
+    }
+
+    fn bans(&self, kind: BanKind) -> StorageFuture<'_, Vec<BanEntry>> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("bans").await?;
+            let entries: HashMap<String, String> = self.check(conn.hgetall(ban_key(kind)).await)?;
+            entries.values().map(|entry| Ok(serde_json::from_str(entry)?)).collect()
+        })
+    }
+
+    fn player_data(&self, uuid: Uuid) -> StorageFuture<'_, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("player_data").await?;
+            self.check(conn.get(player_key(uuid)).await)
+        })
+    }
+
+    fn save_player_data(&self, uuid: Uuid, data: Vec<u8>) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("save_player_data").await?;
+            self.check(conn.set(player_key(uuid), data).await)
+        })
+    }
+
+    fn world_data<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("world_data").await?;
+            self.check(conn.get(world_key(key)).await)
+        })
+    }
+
+    fn save_world_data<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let (mut conn, _timer) = self.connection("save_world_data").await?;
+            self.check(conn.set(world_key(key), data).await)
+        })
+    }
+}